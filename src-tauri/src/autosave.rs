@@ -0,0 +1,188 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::commands;
+use crate::error::Result;
+use crate::grid::GridSnapshot;
+use crate::history::HistoryStatus;
+use crate::project::Project;
+use crate::state::DocumentState;
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(15);
+
+const AUTOSAVE_FILE: &str = "autosave.binblock";
+const SESSION_FILE: &str = "session.json";
+const RECOVERED_FILE: &str = "recovered.binblock";
+const RECOVERED_SESSION_FILE: &str = "recovered-session.json";
+
+/// Written when the app starts and removed when it exits cleanly, so finding
+/// one on launch means the previous run crashed or was killed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Session {
+    document_path: Option<PathBuf>,
+}
+
+/// Payload of the `recovery-available` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryInfo {
+    pub document_path: Option<PathBuf>,
+    pub cols: u32,
+    pub rows: u32,
+    pub modified_at: u64,
+}
+
+/// Autosave and recovery files in the app data dir.
+pub struct Autosave {
+    dir: PathBuf,
+}
+
+impl Autosave {
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Moves an autosave left behind by an unclean shutdown aside so the new
+    /// session can't overwrite it before the user decides what to do with it.
+    fn recover_previous_session(&self) -> io::Result<()> {
+        if !self.path(SESSION_FILE).exists() {
+            return Ok(());
+        }
+        if self.path(AUTOSAVE_FILE).exists() {
+            fs::rename(self.path(AUTOSAVE_FILE), self.path(RECOVERED_FILE))?;
+            fs::rename(self.path(SESSION_FILE), self.path(RECOVERED_SESSION_FILE))?;
+        } else {
+            fs::remove_file(self.path(SESSION_FILE))?;
+        }
+        Ok(())
+    }
+
+    fn write_session(&self, document_path: Option<PathBuf>) -> io::Result<()> {
+        let session = Session { document_path };
+        fs::write(self.path(SESSION_FILE), serde_json::to_vec(&session)?)
+    }
+
+    fn write(&self, project: &Project, document_path: Option<PathBuf>) -> Result<()> {
+        project.save(&self.path(AUTOSAVE_FILE))?;
+        self.write_session(document_path)?;
+        Ok(())
+    }
+
+    /// Removes this session's files. Called on clean exit.
+    pub fn end_session(&self) {
+        remove_if_exists(&self.path(AUTOSAVE_FILE));
+        remove_if_exists(&self.path(SESSION_FILE));
+    }
+
+    /// The recovered project and the path it was last saved to, if a previous
+    /// session crashed with unsaved work.
+    pub fn recovered(&self) -> Result<Option<(Project, Option<PathBuf>)>> {
+        if !self.path(RECOVERED_FILE).exists() {
+            return Ok(None);
+        }
+        let project = Project::load(&self.path(RECOVERED_FILE))?;
+        let session: Session = fs::read(self.path(RECOVERED_SESSION_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        Ok(Some((project, session.document_path)))
+    }
+
+    pub fn discard_recovered(&self) {
+        remove_if_exists(&self.path(RECOVERED_FILE));
+        remove_if_exists(&self.path(RECOVERED_SESSION_FILE));
+    }
+}
+
+fn remove_if_exists(path: &Path) {
+    if let Err(err) = fs::remove_file(path) {
+        if err.kind() != io::ErrorKind::NotFound {
+            eprintln!("failed to remove {}: {err}", path.display());
+        }
+    }
+}
+
+/// Sets up the autosave directory, preserves any crashed session for recovery
+/// and starts the background thread that persists the open document.
+pub fn init(app: &AppHandle) -> Result<()> {
+    let dir = app.path().app_data_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    let autosave = Autosave { dir };
+    autosave.recover_previous_session()?;
+    autosave.write_session(None)?;
+    app.manage(autosave);
+
+    let app = app.clone();
+    thread::spawn(move || loop {
+        thread::sleep(AUTOSAVE_INTERVAL);
+        tick(&app);
+    });
+
+    Ok(())
+}
+
+fn tick(app: &AppHandle) {
+    let state = app.state::<DocumentState>();
+    let (project, path) = {
+        let mut current = state.lock();
+        if !current.autosave_pending {
+            return;
+        }
+        current.autosave_pending = false;
+        (current.project.clone(), current.path.clone())
+    };
+
+    if let Err(err) = app.state::<Autosave>().write(&project, path) {
+        eprintln!("autosave failed: {err}");
+        state.lock().autosave_pending = true;
+    }
+}
+
+/// Emits `recovery-available` if a crashed session left work behind. The
+/// frontend calls this once its listeners are registered.
+#[tauri::command]
+pub fn check_recovery(app: AppHandle) -> Result<()> {
+    if let Some((project, document_path)) = app.state::<Autosave>().recovered()? {
+        let grid = &project.document.grid;
+        app.emit(
+            "recovery-available",
+            RecoveryInfo {
+                document_path,
+                cols: grid.cols(),
+                rows: grid.rows(),
+                modified_at: project.metadata.modified_at,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn restore_recovery(app: AppHandle) -> Result<Option<GridSnapshot>> {
+    let autosave = app.state::<Autosave>();
+    let Some((project, document_path)) = autosave.recovered()? else {
+        return Ok(None);
+    };
+    let snapshot = GridSnapshot::from(&project.document);
+
+    app.state::<DocumentState>()
+        .lock()
+        .replace(project, document_path);
+    commands::history_changed(&app, &HistoryStatus::default());
+
+    autosave.discard_recovered();
+    Ok(Some(snapshot))
+}
+
+#[tauri::command]
+pub fn discard_recovery(app: AppHandle) {
+    app.state::<Autosave>().discard_recovered();
+}
@@ -0,0 +1,587 @@
+//! Headless companion to the binblock++ app for scripts and CI.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use binblock_plusplus_lib::convert::dither::Dither;
+use binblock_plusplus_lib::convert::{self, ConvertOptions};
+use binblock_plusplus_lib::discord::{DiscordOptions, EmojiText, DEFAULT_CHAR_LIMIT};
+use binblock_plusplus_lib::emoji::{EmojiError, EmojiProfile, EmojiProfiles};
+use binblock_plusplus_lib::packs::archive::PackArchive;
+use binblock_plusplus_lib::packs::{Pack, PackLibrary, BUILTIN_PACK_ID};
+use binblock_plusplus_lib::project::Project;
+use binblock_plusplus_lib::render::animation::{self, AnimationFormat};
+use binblock_plusplus_lib::render::svg;
+use binblock_plusplus_lib::render::{self, Color, RenderOptions, Stroke, DEFAULT_CELL_SIZE};
+use binblock_plusplus_lib::text::matrix::{Matrix, MatrixFormat, MatrixOptions};
+use binblock_plusplus_lib::text::slack::{Slack, SlackOptions};
+use binblock_plusplus_lib::text::unicode::{Unicode, UnicodeOptions};
+use binblock_plusplus_lib::text::{LongRows, TextExporter};
+
+/// Where the app installs its bundled block packs, relative to the folder
+/// holding this executable: beside it on Windows, in the bundle's Resources
+/// on macOS and under lib on Linux.
+const BUNDLED_PACKS_DIRS: [&str; 3] = ["packs", "../Resources/packs", "../lib/binblock++/packs"];
+
+#[derive(Parser)]
+#[command(
+    name = "binblock",
+    version,
+    about = "Render and convert binblock++ projects"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    /// Pixels per cell
+    #[arg(long, default_value_t = DEFAULT_CELL_SIZE)]
+    cell_size: u32,
+    /// Draw lines of this colour (#rrggbb or #rrggbbaa) between cells
+    #[arg(long, value_name = "COLOR")]
+    grid_lines: Option<Color>,
+    /// Draw a border of this colour around the grid
+    #[arg(long, value_name = "COLOR")]
+    border: Option<Color>,
+    /// Width in pixels of grid lines and the border
+    #[arg(long, default_value_t = 1)]
+    line_width: u32,
+    /// Fill transparent pixels with this colour
+    #[arg(long, value_name = "COLOR")]
+    background: Option<Color>,
+    #[command(flatten)]
+    packs: PackArgs,
+}
+
+impl RenderArgs {
+    fn options(&self) -> RenderOptions {
+        let stroke = |color| Stroke {
+            color,
+            width: self.line_width,
+        };
+        RenderOptions {
+            cell_size: self.cell_size,
+            background: self.background,
+            grid_lines: self.grid_lines.map(stroke),
+            border: self.border.map(stroke),
+        }
+    }
+}
+
+#[derive(Args)]
+struct PackArgs {
+    /// Folder of block pack folders, like the app's own packs folder; repeat
+    /// to search several, earlier ones winning. Defaults to the packs
+    /// installed with the app
+    #[arg(long = "packs", value_name = "DIR")]
+    dirs: Vec<PathBuf>,
+}
+
+impl PackArgs {
+    fn load(&self) -> Result<PackLibrary, Box<dyn Error>> {
+        if !self.dirs.is_empty() {
+            return Ok(PackLibrary::scan(&self.dirs));
+        }
+        let exe = env::current_exe()?;
+        let dir = exe.parent().and_then(|exe_dir| {
+            BUNDLED_PACKS_DIRS
+                .iter()
+                .map(|dir| exe_dir.join(dir))
+                .find(|dir| dir.is_dir())
+        });
+        match dir {
+            Some(dir) => Ok(PackLibrary::scan(&[dir])),
+            None => {
+                Err("can't find the block packs installed with binblock++; pass --packs DIR".into())
+            }
+        }
+    }
+}
+
+/// Pack `id` from `library`, warning about anything that went wrong loading
+/// it.
+fn load_pack<'a>(library: &'a PackLibrary, id: &str) -> Result<&'a Pack, Box<dyn Error>> {
+    let pack = library.get(id).ok_or_else(|| {
+        let installed: Vec<&str> = library.packs().iter().map(Pack::id).collect();
+        format!(
+            "no block pack {id:?}; installed packs are {}",
+            installed.join(", ")
+        )
+    })?;
+    for problem in library.problems_in(&pack.dir) {
+        eprintln!("binblock: {problem}");
+    }
+    Ok(pack)
+}
+
+#[derive(Args)]
+struct ProfileArgs {
+    /// Emoji profiles file, as saved by the app, to take emoji names from
+    #[arg(long, value_name = "FILE")]
+    profiles: Option<PathBuf>,
+    /// Profile to use from `--profiles`; defaults to the active one
+    #[arg(long, value_name = "NAME", requires = "profiles")]
+    profile: Option<String>,
+}
+
+impl ProfileArgs {
+    fn load(&self) -> Result<Option<EmojiProfile>, Box<dyn Error>> {
+        let Some(path) = &self.profiles else {
+            return Ok(None);
+        };
+        let mut profiles = EmojiProfiles::load(path)?;
+        let name = self
+            .profile
+            .clone()
+            .or(profiles.active.clone())
+            .ok_or("no --profile given and the profiles file has no active profile")?;
+        let profile = profiles
+            .profiles
+            .remove(&name)
+            .ok_or(EmojiError::UnknownProfile(name))?;
+        Ok(Some(profile))
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TextFormat {
+    /// `:name:` emoji for Slack
+    Slack,
+    /// `:shortcode:` emoji for Matrix
+    Matrix,
+    /// Inline `<img>` HTML for Matrix, using the images given by `--mxc`
+    MatrixHtml,
+    /// Coloured square emoji that work anywhere
+    Unicode,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DitherArg {
+    None,
+    FloydSteinberg,
+    Atkinson,
+    Bayer,
+}
+
+impl From<DitherArg> for Dither {
+    fn from(dither: DitherArg) -> Self {
+        match dither {
+            DitherArg::None => Dither::None,
+            DitherArg::FloydSteinberg => Dither::FloydSteinberg,
+            DitherArg::Atkinson => Dither::Atkinson,
+            DitherArg::Bayer => Dither::Bayer,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Render a project to a PNG image
+    Render {
+        file: PathBuf,
+        /// Where to write the PNG
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        render: RenderArgs,
+    },
+    /// Render one loop of a project's animated blocks to a GIF or APNG
+    Animate {
+        file: PathBuf,
+        /// Where to write the animation; `.gif` writes a GIF, `.png` or
+        /// `.apng` an APNG
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        render: RenderArgs,
+    },
+    /// Render a project to an SVG with the block images embedded
+    Svg {
+        file: PathBuf,
+        /// Where to write the SVG
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        render: RenderArgs,
+    },
+    /// Print a project as Discord emoji text
+    Discord {
+        file: PathBuf,
+        /// Maximum characters per message (4000 with Nitro)
+        #[arg(long, default_value_t = DEFAULT_CHAR_LIMIT)]
+        limit: usize,
+        /// Wrap rows longer than the limit onto several lines instead of
+        /// failing
+        #[arg(long)]
+        wrap: bool,
+        #[command(flatten)]
+        profile: ProfileArgs,
+    },
+    /// Print a project as Slack, Matrix or Unicode emoji text
+    Text {
+        file: PathBuf,
+        #[arg(long, value_enum)]
+        format: TextFormat,
+        /// Maximum message length; characters, or bytes for Matrix.
+        /// Defaults to the target's own limit
+        #[arg(long)]
+        limit: Option<usize>,
+        /// Wrap rows longer than the limit instead of failing
+        #[arg(long)]
+        wrap: bool,
+        #[command(flatten)]
+        profile: ProfileArgs,
+        /// JSON file mapping block ids to uploaded `mxc://` images, for
+        /// `--format matrix-html`
+        #[arg(long, value_name = "FILE")]
+        mxc: Option<PathBuf>,
+        /// Where to find the project's pack, to pick Unicode colours from
+        #[command(flatten)]
+        packs: PackArgs,
+    },
+    /// Read Discord emoji text back into a project
+    ImportDiscord {
+        /// Text file of pasted messages, or `-` for stdin
+        file: PathBuf,
+        /// Where to write the project
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        profile: ProfileArgs,
+        /// Block pack to draw the project with, whose blocks bare
+        /// `:blockId:` emoji may name
+        #[arg(long, value_name = "ID", default_value = BUILTIN_PACK_ID)]
+        pack: String,
+        #[command(flatten)]
+        packs: PackArgs,
+    },
+    /// Rebuild a picture out of blocks as a new project
+    Convert {
+        image: PathBuf,
+        /// Where to write the project
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(long, default_value_t = 16)]
+        cols: u32,
+        #[arg(long, default_value_t = 16)]
+        rows: u32,
+        /// Match each quarter of a cell as well as its average colour
+        #[arg(long)]
+        texture: bool,
+        #[arg(long, value_enum, default_value = "none")]
+        dither: DitherArg,
+        /// Only pick from these block ids, comma separated
+        #[arg(long, value_name = "IDS", value_delimiter = ',')]
+        only: Option<Vec<String>>,
+        /// Block pack to draw the project with
+        #[arg(long, value_name = "ID", default_value = BUILTIN_PACK_ID)]
+        pack: String,
+        #[command(flatten)]
+        packs: PackArgs,
+    },
+    /// Bundle a block pack folder into a .binpack file to share
+    Pack {
+        /// Folder holding the pack's pack.json
+        dir: PathBuf,
+        /// Where to write the .binpack
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Take the pack's emoji map from a profile instead of its own
+        /// emoji.json
+        #[command(flatten)]
+        profile: ProfileArgs,
+    },
+    /// Show a project's size, blocks and metadata
+    Info { file: PathBuf },
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("binblock: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Render {
+            file,
+            output,
+            render,
+        } => {
+            let project = Project::load(&file)?;
+            let library = render.packs.load()?;
+            let blocks = &load_pack(&library, &project.document.pack.id)?.blocks;
+            let image = render::render(&project.document, blocks, &render.options())?;
+            image.save(&output)?;
+            println!(
+                "wrote {} ({}x{})",
+                output.display(),
+                image.width(),
+                image.height()
+            );
+        }
+        Command::Animate {
+            file,
+            output,
+            render,
+        } => {
+            let format = match output.extension().and_then(|ext| ext.to_str()) {
+                Some("gif") => AnimationFormat::Gif,
+                Some("png" | "apng") => AnimationFormat::Apng,
+                _ => {
+                    return Err(format!(
+                        "can't tell the format of {} from its extension",
+                        output.display()
+                    )
+                    .into())
+                }
+            };
+            let project = Project::load(&file)?;
+            let library = render.packs.load()?;
+            let blocks = &load_pack(&library, &project.document.pack.id)?.blocks;
+            let writer = BufWriter::new(File::create(&output)?);
+            let timeline =
+                animation::encode(&project.document, blocks, &render.options(), format, writer)?;
+            println!(
+                "wrote {} ({} frames, {}ms loop)",
+                output.display(),
+                timeline.len(),
+                timeline.length_ms()
+            );
+        }
+        Command::Svg {
+            file,
+            output,
+            render,
+        } => {
+            let project = Project::load(&file)?;
+            let library = render.packs.load()?;
+            let blocks = &load_pack(&library, &project.document.pack.id)?.blocks;
+            let svg = svg::render_svg(&project.document, blocks, &render.options())?;
+            fs::write(&output, svg)?;
+            println!("wrote {}", output.display());
+        }
+        Command::Discord {
+            file,
+            limit,
+            wrap,
+            profile,
+        } => {
+            let project = Project::load(&file)?;
+            let document = &project.document;
+            let options = DiscordOptions {
+                char_limit: limit,
+                long_rows: if wrap {
+                    LongRows::Wrap
+                } else {
+                    LongRows::Error
+                },
+            };
+            let text = match profile.load()? {
+                Some(profile) => {
+                    let unmapped = profile.unmapped(document);
+                    if !unmapped.is_empty() {
+                        eprintln!("binblock: no emoji mapped for {}", unmapped.join(", "));
+                    }
+                    EmojiText::with_profile(document, &profile)
+                }
+                None => EmojiText::from_document(document),
+            };
+            print_messages(&text.chunks(&options)?);
+        }
+        Command::Text {
+            file,
+            format,
+            limit,
+            wrap,
+            profile,
+            mxc,
+            packs,
+        } => {
+            let project = Project::load(&file)?;
+            let document = &project.document;
+            let profile = profile.load()?;
+            let long_rows = if wrap {
+                LongRows::Wrap
+            } else {
+                LongRows::Error
+            };
+            let messages = match format {
+                TextFormat::Slack => {
+                    let defaults = SlackOptions::default();
+                    let options = SlackOptions {
+                        char_limit: limit.unwrap_or(defaults.char_limit),
+                        long_rows,
+                    };
+                    Slack {
+                        profile: profile.as_ref(),
+                        options,
+                    }
+                    .messages(document)?
+                }
+                TextFormat::Matrix | TextFormat::MatrixHtml => {
+                    let defaults = MatrixOptions::default();
+                    let options = MatrixOptions {
+                        format: match format {
+                            TextFormat::MatrixHtml => MatrixFormat::Html,
+                            _ => MatrixFormat::Shortcodes,
+                        },
+                        sources: match mxc {
+                            Some(path) => serde_json::from_slice(&fs::read(path)?)?,
+                            None => BTreeMap::new(),
+                        },
+                        byte_limit: limit.unwrap_or(defaults.byte_limit),
+                        long_rows,
+                    };
+                    Matrix {
+                        profile: profile.as_ref(),
+                        options: &options,
+                    }
+                    .messages(document)?
+                }
+                TextFormat::Unicode => {
+                    let defaults = UnicodeOptions::default();
+                    let options = UnicodeOptions {
+                        char_limit: limit.unwrap_or(defaults.char_limit),
+                        long_rows,
+                    };
+                    let library = packs.load()?;
+                    let blocks = &load_pack(&library, &document.pack.id)?.blocks;
+                    Unicode::new(blocks, options).messages(document)?
+                }
+            };
+            print_messages(&messages);
+        }
+        Command::ImportDiscord {
+            file,
+            output,
+            profile,
+            pack,
+            packs,
+        } => {
+            let text = if file.as_os_str() == "-" {
+                io::read_to_string(io::stdin())?
+            } else {
+                fs::read_to_string(&file)?
+            };
+            let library = packs.load()?;
+            let pack = load_pack(&library, &pack)?;
+            let imported = EmojiText::parse(&text)?.to_document(
+                profile.load()?.as_ref(),
+                &pack.blocks,
+                pack.pack_ref(),
+            )?;
+            if !imported.unknown.is_empty() {
+                eprintln!(
+                    "binblock: left cells empty for unknown emoji {}",
+                    imported.unknown.join(", ")
+                );
+            }
+            let project = Project {
+                document: imported.document,
+                ..Project::default()
+            };
+            project.save(&output)?;
+            let grid = &project.document.grid;
+            println!(
+                "wrote {} ({}x{})",
+                output.display(),
+                grid.cols(),
+                grid.rows()
+            );
+        }
+        Command::Convert {
+            image,
+            output,
+            cols,
+            rows,
+            texture,
+            dither,
+            only,
+            pack,
+            packs,
+        } => {
+            let image = image::open(&image)?.into_rgba8();
+            let options = ConvertOptions {
+                cols,
+                rows,
+                match_texture: texture,
+                dither: dither.into(),
+                blocks: only,
+            };
+            let library = packs.load()?;
+            let pack = load_pack(&library, &pack)?;
+            let project = Project {
+                document: convert::convert(&image, &pack.blocks, &options, pack.pack_ref())?,
+                ..Project::default()
+            };
+            project.save(&output)?;
+            println!("wrote {} ({cols}x{rows})", output.display());
+        }
+        Command::Pack {
+            dir,
+            output,
+            profile,
+        } => {
+            let archive = PackArchive::from_dir(&dir, profile.load()?.as_ref())?;
+            archive.write(BufWriter::new(File::create(&output)?))?;
+            println!(
+                "wrote {} ({} blocks)",
+                output.display(),
+                archive.images.len()
+            );
+        }
+        Command::Info { file } => print_info(&file)?,
+    }
+    Ok(())
+}
+
+fn print_messages(messages: &[String]) {
+    for (index, message) in messages.iter().enumerate() {
+        if messages.len() > 1 {
+            println!("--- part {} of {} ---", index + 1, messages.len());
+        }
+        println!("{message}");
+    }
+}
+
+fn print_info(file: &Path) -> Result<(), Box<dyn Error>> {
+    let Project { document, metadata } = Project::load(file)?;
+    let grid = &document.grid;
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for y in 0..grid.rows() {
+        for x in 0..grid.cols() {
+            *counts.entry(document.block_at(x, y)?).or_default() += 1;
+        }
+    }
+
+    println!("file:        {}", file.display());
+    if let Some(title) = &metadata.title {
+        println!("title:       {title}");
+    }
+    println!("size:        {}x{}", grid.cols(), grid.rows());
+    println!(
+        "block pack:  {} (default block {})",
+        document.pack.id, document.pack.default_block
+    );
+    println!("saved by:    binblock++ {}", metadata.app_version);
+    println!("blocks used: {}", counts.len());
+    for (id, count) in counts {
+        println!("  {id:<24} {count}");
+    }
+    Ok(())
+}
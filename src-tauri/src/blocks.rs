@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+use image::codecs::gif::GifDecoder;
+use image::{AnimationDecoder, RgbaImage};
+
+/// Browsers show GIF frames with a delay of 10ms or less for 100ms, and
+/// blocks are drawn to look right there.
+const MIN_FRAME_DELAY_MS: u32 = 20;
+const DEFAULT_FRAME_DELAY_MS: u32 = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlockError {
+    #[error("failed to read block image {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("failed to decode block image {}: {source}", path.display())]
+    Image {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    #[error("block image {} has no frames", path.display())]
+    NoFrames { path: PathBuf },
+}
+
+/// One frame of a block and how long it stays on screen.
+#[derive(Debug, Clone)]
+pub struct BlockFrame {
+    pub image: RgbaImage,
+    pub delay_ms: u32,
+}
+
+/// A block's image. Still blocks have one frame; animated GIF blocks loop
+/// through all of theirs.
+#[derive(Debug, Clone)]
+pub struct Block {
+    frames: Vec<BlockFrame>,
+}
+
+impl Block {
+    pub fn still(image: RgbaImage) -> Self {
+        Self {
+            frames: vec![BlockFrame { image, delay_ms: 0 }],
+        }
+    }
+
+    /// Returns `None` if `frames` is empty.
+    pub fn animated(frames: Vec<BlockFrame>) -> Option<Self> {
+        (!frames.is_empty()).then_some(Self { frames })
+    }
+
+    /// The first frame, used wherever only a still image makes sense.
+    pub fn image(&self) -> &RgbaImage {
+        &self.frames[0].image
+    }
+
+    pub fn frames(&self) -> &[BlockFrame] {
+        &self.frames
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    /// Length of one loop through the frames, or 0 for still blocks.
+    pub fn duration_ms(&self) -> u64 {
+        if !self.is_animated() {
+            return 0;
+        }
+        self.frames
+            .iter()
+            .map(|frame| u64::from(frame.delay_ms))
+            .sum()
+    }
+
+    /// The frame showing `time_ms` after the animation started.
+    pub fn frame_at(&self, time_ms: u64) -> &RgbaImage {
+        let duration = self.duration_ms();
+        if duration == 0 {
+            return self.image();
+        }
+        let mut remaining = time_ms % duration;
+        for frame in &self.frames {
+            let delay = u64::from(frame.delay_ms);
+            if remaining < delay {
+                return &frame.image;
+            }
+            remaining -= delay;
+        }
+        self.image()
+    }
+
+    /// Loads a block image; `.gif` files keep all their frames.
+    pub fn load(path: &Path) -> Result<Self, BlockError> {
+        let image_err = |source| BlockError::Image {
+            path: path.to_owned(),
+            source,
+        };
+
+        if extension(path) != Some("gif") {
+            return Ok(Self::still(
+                image::open(path).map_err(image_err)?.into_rgba8(),
+            ));
+        }
+
+        let file = File::open(path).map_err(|source| BlockError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        let frames = GifDecoder::new(BufReader::new(file))
+            .and_then(|decoder| decoder.into_frames().collect_frames())
+            .map_err(image_err)?
+            .into_iter()
+            .map(|frame| {
+                let (numer, denom) = frame.delay().numer_denom_ms();
+                let delay_ms = numer / denom.max(1);
+                BlockFrame {
+                    image: frame.into_buffer(),
+                    delay_ms: if delay_ms < MIN_FRAME_DELAY_MS {
+                        DEFAULT_FRAME_DELAY_MS
+                    } else {
+                        delay_ms
+                    },
+                }
+            })
+            .collect();
+        Self::animated(frames).ok_or_else(|| BlockError::NoFrames {
+            path: path.to_owned(),
+        })
+    }
+}
+
+/// Blocks keyed by id. A block's id is the stem of its file name.
+#[derive(Debug, Clone, Default)]
+pub struct BlockSet {
+    blocks: BTreeMap<String, Block>,
+}
+
+impl BlockSet {
+    pub fn insert(&mut self, id: impl Into<String>, block: Block) {
+        self.blocks.insert(id.into(), block);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Block> {
+        self.blocks.get(id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.blocks.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+}
+
+fn extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(|ext| ext.to_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    fn frame(value: u8, delay_ms: u32) -> BlockFrame {
+        BlockFrame {
+            image: RgbaImage::from_pixel(1, 1, Rgba([value; 4])),
+            delay_ms,
+        }
+    }
+
+    #[test]
+    fn animated_blocks_loop_through_frames() {
+        let block = Block::animated(vec![frame(1, 100), frame(2, 50)]).unwrap();
+        assert_eq!(block.duration_ms(), 150);
+        assert_eq!(block.frame_at(0)[(0, 0)], Rgba([1; 4]));
+        assert_eq!(block.frame_at(99)[(0, 0)], Rgba([1; 4]));
+        assert_eq!(block.frame_at(100)[(0, 0)], Rgba([2; 4]));
+        assert_eq!(block.frame_at(160)[(0, 0)], Rgba([1; 4]));
+    }
+
+    #[test]
+    fn still_blocks_have_no_duration() {
+        let block = Block::still(frame(3, 0).image);
+        assert!(!block.is_animated());
+        assert_eq!(block.duration_ms(), 0);
+        assert_eq!(block.frame_at(1234)[(0, 0)], Rgba([3; 4]));
+        assert!(Block::animated(Vec::new()).is_none());
+    }
+}
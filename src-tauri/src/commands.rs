@@ -0,0 +1,382 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+
+use crate::convert::{self, ConvertOptions};
+use crate::discord::EmojiText;
+use crate::error::{Error, Result};
+use crate::grid::{Document, GridError, GridSnapshot};
+use crate::history::{History, HistoryStatus};
+use crate::menu;
+use crate::packs::archive::PackArchive;
+use crate::packs::import::{self, USER_PACK_ID};
+use crate::packs::search::{self, BlockMatch};
+use crate::packs::watch::BlocksChanged;
+use crate::packs::{PackCatalog, PackError};
+use crate::project::{Project, ProjectError};
+use crate::recent::RecentFiles;
+use crate::state::{BlockState, DocumentState, EmojiProfileState, OpenDocument, RecentState};
+
+/// Replaces the open project with a blank default grid.
+#[tauri::command]
+pub fn new_document(app: AppHandle) -> GridSnapshot {
+    let project = Project::default();
+    let snapshot = GridSnapshot::from(&project.document);
+    app.state::<DocumentState>().lock().replace(project, None);
+    history_changed(&app, &HistoryStatus::default());
+    snapshot
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedGrid {
+    grid: GridSnapshot,
+    /// Emoji that matched no block; their cells were left empty.
+    unknown: Vec<String>,
+}
+
+/// Id of pack `pack`, or of the open document's pack if that's `None`.
+fn pack_or_current(pack: Option<String>, app: &AppHandle) -> String {
+    pack.unwrap_or_else(|| {
+        let state = app.state::<DocumentState>();
+        let id = state.lock().project.document.pack.id.clone();
+        id
+    })
+}
+
+/// Replaces the open project with a grid read from pasted Discord emoji
+/// text, mapping emoji back to blocks of pack `pack`, or of the open
+/// document's pack, through the active emoji profile.
+#[tauri::command]
+pub fn import_discord_text(
+    text: String,
+    pack: Option<String>,
+    app: AppHandle,
+) -> Result<ImportedGrid> {
+    let pack_id = pack_or_current(pack, &app);
+    let profiles = app.state::<EmojiProfileState>().get();
+    let library = app.state::<BlockState>();
+    let library = library.lock();
+    let pack = library
+        .get(&pack_id)
+        .ok_or(PackError::UnknownPack(pack_id))?;
+    let imported = EmojiText::parse(&text)?.to_document(
+        profiles.active_profile(),
+        &pack.blocks,
+        pack.pack_ref(),
+    )?;
+
+    let project = Project {
+        document: imported.document,
+        ..Project::default()
+    };
+    let grid = GridSnapshot::from(&project.document);
+    app.state::<DocumentState>().lock().replace(project, None);
+    history_changed(&app, &HistoryStatus::default());
+    Ok(ImportedGrid {
+        grid,
+        unknown: imported.unknown,
+    })
+}
+
+/// Replaces the open project with the picture at `path` rebuilt from the
+/// blocks of pack `pack`, or of the open document's pack.
+#[tauri::command]
+pub fn import_image(
+    path: PathBuf,
+    options: ConvertOptions,
+    pack: Option<String>,
+    app: AppHandle,
+) -> Result<GridSnapshot> {
+    let pack_id = pack_or_current(pack, &app);
+    let image = image::open(&path)?.into_rgba8();
+    let library = app.state::<BlockState>();
+    let library = library.lock();
+    let pack = library
+        .get(&pack_id)
+        .ok_or(PackError::UnknownPack(pack_id))?;
+    let document = convert::convert(&image, &pack.blocks, &options, pack.pack_ref())?;
+
+    let project = Project {
+        document,
+        ..Project::default()
+    };
+    let snapshot = GridSnapshot::from(&project.document);
+    app.state::<DocumentState>().lock().replace(project, None);
+    history_changed(&app, &HistoryStatus::default());
+    Ok(snapshot)
+}
+
+/// Every loaded block pack, with the problems found loading them.
+#[tauri::command]
+pub fn block_catalog(app: AppHandle) -> PackCatalog {
+    app.state::<BlockState>().lock().catalog()
+}
+
+/// Blocks whose id, name, tags or aliases fuzzily match `query`, best first.
+/// `pack` limits the search to one pack.
+#[tauri::command]
+pub fn search_blocks(query: String, pack: Option<String>, app: AppHandle) -> Vec<BlockMatch> {
+    search::search(&app.state::<BlockState>().lock(), &query, pack.as_deref())
+}
+
+/// Adds the pictures at `paths` to the user's custom block pack, emits
+/// `blocks-changed` and returns the ids they were given.
+#[tauri::command]
+pub fn import_block(paths: Vec<PathBuf>, app: AppHandle) -> Result<Vec<String>> {
+    let blocks = app.state::<BlockState>();
+    let dir = blocks.user_dir().join(USER_PACK_ID);
+    let ids = import::import_blocks(&dir, &paths)?;
+    let packs = blocks.reload([dir.as_path()]);
+    app.emit("blocks-changed", BlocksChanged { packs })?;
+    Ok(ids)
+}
+
+/// Writes block pack `pack_id` to a `.binpack` at `path`. The active emoji
+/// profile's emoji for its blocks go along with it; without an active
+/// profile, the pack's own emoji map does.
+#[tauri::command]
+pub fn export_block_pack(pack_id: String, path: PathBuf, app: AppHandle) -> Result<()> {
+    let profiles = app.state::<EmojiProfileState>().get();
+    let dir = app
+        .state::<BlockState>()
+        .lock()
+        .get(&pack_id)
+        .map(|pack| pack.dir.clone())
+        .ok_or(PackError::UnknownPack(pack_id))?;
+    let archive = PackArchive::from_dir(&dir, profiles.active_profile())?;
+    archive.write(BufWriter::new(File::create(&path)?))?;
+    Ok(())
+}
+
+/// Installs the `.binpack` at `path` into the user's packs and returns the
+/// new pack's id. Its emoji map, if it has one, is saved as an emoji profile
+/// named after the pack unless a profile by that name already exists.
+#[tauri::command]
+pub fn import_block_pack(path: PathBuf, app: AppHandle) -> Result<String> {
+    let archive = PackArchive::read(BufReader::new(File::open(&path)?))?;
+    let blocks = app.state::<BlockState>();
+    let dir = archive.install(blocks.user_dir(), &blocks.lock())?;
+    let packs = blocks.reload([dir.as_path()]);
+
+    let PackArchive {
+        manifest, emoji, ..
+    } = archive;
+    if let Some(emoji) = emoji {
+        app.state::<EmojiProfileState>().update(|profiles| {
+            if profiles.profiles.contains_key(&manifest.name) {
+                return Ok(());
+            }
+            profiles.insert(&manifest.name, emoji)
+        })?;
+    }
+    app.emit("blocks-changed", BlocksChanged { packs })?;
+    Ok(manifest.id)
+}
+
+/// Switches the open document to block pack `pack_id`. Empty cells take
+/// the new pack's default block; painted cells keep their ids, so any the
+/// pack doesn't have are reported missing until they're repainted.
+#[tauri::command]
+pub fn set_document_pack(pack_id: String, app: AppHandle) -> Result<GridSnapshot> {
+    let pack = app
+        .state::<BlockState>()
+        .lock()
+        .get(&pack_id)
+        .ok_or(PackError::UnknownPack(pack_id))?
+        .pack_ref();
+
+    let state = app.state::<DocumentState>();
+    let mut current = state.lock();
+    current.project.document.pack = pack;
+    current.autosave_pending = true;
+    Ok(GridSnapshot::from(&current.project.document))
+}
+
+#[tauri::command]
+pub fn open_document(path: PathBuf, app: AppHandle) -> Result<GridSnapshot> {
+    let project = Project::load(&path)?;
+    let snapshot = GridSnapshot::from(&project.document);
+
+    app.state::<DocumentState>()
+        .lock()
+        .replace(project, Some(path.clone()));
+    history_changed(&app, &HistoryStatus::default());
+
+    remember_recent(&app, |files| files.add(path));
+    Ok(snapshot)
+}
+
+/// Opens the `index`th entry of the Open Recent menu, dropping it from the
+/// menu if the file is gone. Any other failure leaves the entry in place,
+/// since the file may well open later.
+#[tauri::command]
+pub fn open_recent(index: usize, app: AppHandle) -> Result<GridSnapshot> {
+    let paths = app.state::<RecentState>().paths();
+    let Some(path) = paths.get(index).cloned() else {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "recent file no longer listed").into());
+    };
+
+    let result = open_document(path.clone(), app.clone());
+    let missing = matches!(
+        &result,
+        Err(Error::Project(ProjectError::Io(err))) if err.kind() == io::ErrorKind::NotFound
+    );
+    if missing {
+        remember_recent(&app, |files| files.remove(&path));
+    }
+    result
+}
+
+/// Saves the open document to `path`. Its modified time is only updated
+/// once the file has been written.
+#[tauri::command]
+pub fn save_document(path: PathBuf, app: AppHandle) -> Result<()> {
+    let state = app.state::<DocumentState>();
+    let mut current = state.lock();
+    let mut saved = current.project.clone();
+    saved.metadata.touch();
+    saved.save(&path)?;
+    current.project.metadata = saved.metadata;
+    current.path = Some(path.clone());
+    drop(current);
+
+    remember_recent(&app, |files| files.add(path));
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellEdit {
+    x: u32,
+    y: u32,
+    block_id: Option<String>,
+}
+
+/// Applies one editing operation (a stroke, shape, fill or clear) from the
+/// frontend and records it as a single undo step.
+#[tauri::command]
+pub fn apply_edit(
+    label: String,
+    cells: Vec<CellEdit>,
+    app: AppHandle,
+    state: State<'_, DocumentState>,
+) -> Result<HistoryStatus> {
+    let mut current = state.lock();
+    let OpenDocument {
+        project, history, ..
+    } = &mut *current;
+    let cells = cells.into_iter().map(|c| (c.x, c.y, c.block_id));
+    if !history.edit(&mut project.document, label, cells)? {
+        return Ok(history.status());
+    }
+    current.autosave_pending = true;
+    let status = current.history.status();
+    drop(current);
+
+    history_changed(&app, &status);
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn resize_document(
+    cols: u32,
+    rows: u32,
+    app: AppHandle,
+    state: State<'_, DocumentState>,
+) -> Result<GridSnapshot> {
+    let mut current = state.lock();
+    let OpenDocument {
+        project, history, ..
+    } = &mut *current;
+    history.resize(&mut project.document, cols, rows, "Resize Grid")?;
+    current.autosave_pending = true;
+    let snapshot = GridSnapshot::from(&current.project.document);
+    let status = current.history.status();
+    drop(current);
+
+    history_changed(&app, &status);
+    Ok(snapshot)
+}
+
+/// Reverts the last edit and emits `document-changed` with the new grid.
+#[tauri::command]
+pub fn undo<R: Runtime>(app: AppHandle<R>) -> Result<Option<GridSnapshot>> {
+    step_history(&app, History::undo)
+}
+
+/// Re-applies the last undone edit and emits `document-changed`.
+#[tauri::command]
+pub fn redo<R: Runtime>(app: AppHandle<R>) -> Result<Option<GridSnapshot>> {
+    step_history(&app, History::redo)
+}
+
+/// Emits `document-changed` with the open grid so the frontend can drop
+/// edits the backend turned down, such as after `apply_edit` fails.
+#[tauri::command]
+pub fn resync_document<R: Runtime>(app: AppHandle<R>) -> Result<GridSnapshot> {
+    let snapshot = GridSnapshot::from(&app.state::<DocumentState>().lock().project.document);
+    app.emit("document-changed", &snapshot)?;
+    Ok(snapshot)
+}
+
+#[tauri::command]
+pub fn can_undo(state: State<'_, DocumentState>) -> bool {
+    state.lock().history.can_undo()
+}
+
+#[tauri::command]
+pub fn can_redo(state: State<'_, DocumentState>) -> bool {
+    state.lock().history.can_redo()
+}
+
+fn step_history<R: Runtime>(
+    app: &AppHandle<R>,
+    step: fn(&mut History, &mut Document) -> std::result::Result<Option<String>, GridError>,
+) -> Result<Option<GridSnapshot>> {
+    let state = app.state::<DocumentState>();
+    let mut current = state.lock();
+    let OpenDocument {
+        project, history, ..
+    } = &mut *current;
+    if step(history, &mut project.document)?.is_none() {
+        return Ok(None);
+    }
+    current.autosave_pending = true;
+    let snapshot = GridSnapshot::from(&current.project.document);
+    let status = current.history.status();
+    drop(current);
+
+    history_changed(app, &status);
+    app.emit("document-changed", &snapshot)?;
+    Ok(Some(snapshot))
+}
+
+/// Refreshes the Undo/Redo menu items. Called after every history change.
+pub fn history_changed<R: Runtime>(app: &AppHandle<R>, status: &HistoryStatus) {
+    if let Err(err) = menu::set_history_status(app, status) {
+        eprintln!("failed to update undo/redo menu items: {err}");
+    }
+}
+
+/// Path of the open project, or `None` if it has never been saved.
+#[tauri::command]
+pub fn document_path(state: State<'_, DocumentState>) -> Option<PathBuf> {
+    state.lock().path.clone()
+}
+
+/// Updates the recent files list and the Open Recent menu. Failures are only
+/// logged since they shouldn't fail the open or save that triggered them.
+fn remember_recent(app: &AppHandle, change: impl FnOnce(&mut RecentFiles)) {
+    let result = app
+        .state::<RecentState>()
+        .update(change)
+        .map_err(tauri::Error::from)
+        .and_then(|paths| menu::set_recent_files(app, &paths));
+    if let Err(err) = result {
+        eprintln!("failed to update recent files: {err}");
+    }
+}
@@ -0,0 +1,536 @@
+//! Turning pictures into grids. The picture is stretched over the grid, split
+//! into one rectangle per cell, and every rectangle gets the block whose
+//! average colour is closest in CIELAB. With texture matching, rectangles and
+//! blocks are also compared quadrant by quadrant, so a block that is dark on
+//! one side can beat an evenly coloured one with the same average. Cells are
+//! converted row by row, optionally dithered (see [`dither`]).
+
+pub mod dither;
+pub mod lab;
+
+use std::sync::OnceLock;
+
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use self::dither::{bayer_threshold, Diffusion, Dither};
+use self::lab::{srgb_to_linear, Lab};
+use crate::blocks::BlockSet;
+use crate::grid::{BlockPackRef, Document, GridError};
+
+/// Cells less than half covered by opaque pixels are left empty.
+const MIN_COVERAGE: f32 = 0.5;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConvertError {
+    #[error("there are no blocks with visible pixels to match against")]
+    NoBlocks,
+    #[error("no block with id {0:?}")]
+    UnknownBlock(String),
+    #[error(transparent)]
+    Grid(#[from] GridError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/", optional_fields)]
+pub struct ConvertOptions {
+    pub cols: u32,
+    pub rows: u32,
+    /// Compare each quadrant of a cell as well as its average colour.
+    #[serde(default)]
+    pub match_texture: bool,
+    #[serde(default)]
+    pub dither: Dither,
+    /// Only pick from these blocks; every loaded block when missing.
+    #[serde(default)]
+    pub blocks: Option<Vec<String>>,
+}
+
+/// A colour in linear light, channels in `0.0..=1.0`.
+type Linear = [f32; 3];
+
+/// The colours of an area in linear light: its average, and the average of
+/// each quadrant from the top left to the bottom right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    mean: Linear,
+    quadrants: [Linear; 4],
+}
+
+impl Sample {
+    /// Sample of the `x`, `y` rectangle of `image` and the fraction of it
+    /// covered by opaque pixels, or `None` if it is fully transparent.
+    fn of(image: &RgbaImage, x: (u32, u32), y: (u32, u32)) -> Option<(Self, f32)> {
+        let (mean, coverage) = average(image, x, y)?;
+        let [left, right] = halves(x);
+        let [top, bottom] = halves(y);
+        let quadrants = [(left, top), (right, top), (left, bottom), (right, bottom)]
+            .map(|(x, y)| average(image, x, y).map_or(mean, |(color, _)| color));
+        Some((Self { mean, quadrants }, coverage))
+    }
+
+    /// Every colour moved by `offset`, then kept within `low..=high` on each
+    /// channel.
+    fn shifted(&self, offset: Linear, (low, high): (Linear, Linear)) -> Self {
+        let shift =
+            |color: Linear| std::array::from_fn(|i| (color[i] + offset[i]).clamp(low[i], high[i]));
+        Self {
+            mean: shift(self.mean),
+            quadrants: self.quadrants.map(shift),
+        }
+    }
+
+    fn signature(&self) -> Signature {
+        Signature {
+            mean: Lab::from_linear(self.mean),
+            quadrants: self.quadrants.map(Lab::from_linear),
+        }
+    }
+}
+
+/// A [`Sample`] in CIELAB, for comparing how alike two areas look.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Signature {
+    pub mean: Lab,
+    pub quadrants: [Lab; 4],
+}
+
+impl Signature {
+    fn distance_squared(&self, other: &Signature, match_texture: bool) -> f32 {
+        if !match_texture {
+            return self.mean.distance_squared(other.mean);
+        }
+        self.quadrants
+            .iter()
+            .zip(&other.quadrants)
+            .map(|(a, b)| a.distance_squared(*b))
+            .sum::<f32>()
+            / 4.0
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    id: String,
+    color: Linear,
+    signature: Signature,
+}
+
+/// Blocks to pick from and their signatures.
+#[derive(Debug, Clone, Default)]
+pub struct Palette {
+    blocks: Vec<Entry>,
+}
+
+impl Palette {
+    /// Takes every block in `blocks` with any visible pixels, using the first
+    /// frame of animated ones.
+    pub fn new(blocks: &BlockSet) -> Self {
+        Self {
+            blocks: blocks.ids().filter_map(|id| entry(blocks, id)).collect(),
+        }
+    }
+
+    /// Like [`Palette::new`], but only takes the blocks named in `ids`.
+    pub fn with_ids(blocks: &BlockSet, ids: &[String]) -> Result<Self, ConvertError> {
+        let mut palette = Self::default();
+        for id in ids {
+            if blocks.get(id).is_none() {
+                return Err(ConvertError::UnknownBlock(id.clone()));
+            }
+            if palette.blocks.iter().all(|entry| entry.id != *id) {
+                palette.blocks.extend(entry(blocks, id));
+            }
+        }
+        Ok(palette)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// The block that looks most like `signature`, or `None` if the palette
+    /// is empty.
+    pub fn nearest(&self, signature: &Signature, match_texture: bool) -> Option<&str> {
+        self.nearest_entry(signature, match_texture)
+            .map(|entry| entry.id.as_str())
+    }
+
+    fn nearest_entry(&self, signature: &Signature, match_texture: bool) -> Option<&Entry> {
+        self.blocks.iter().min_by(|a, b| {
+            let a = signature.distance_squared(&a.signature, match_texture);
+            let b = signature.distance_squared(&b.signature, match_texture);
+            a.total_cmp(&b)
+        })
+    }
+
+    /// The darkest and brightest value of each channel over every block.
+    fn bounds(&self) -> (Linear, Linear) {
+        self.blocks.iter().fold(
+            ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+            |(low, high), entry| {
+                (
+                    std::array::from_fn(|i| low[i].min(entry.color[i])),
+                    std::array::from_fn(|i| high[i].max(entry.color[i])),
+                )
+            },
+        )
+    }
+
+    /// Average distance in linear light from each block to the closest
+    /// other one, which is how far ordered dithering nudges a cell.
+    fn spacing(&self) -> f32 {
+        if self.blocks.len() < 2 {
+            return 0.0;
+        }
+        let total: f32 = self
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, a)| {
+                self.blocks
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .map(|(_, b)| distance(a.color, b.color))
+                    .fold(f32::INFINITY, f32::min)
+            })
+            .sum();
+        total / self.blocks.len() as f32
+    }
+}
+
+fn entry(blocks: &BlockSet, id: &str) -> Option<Entry> {
+    let image = blocks.get(id)?.image();
+    let (sample, _) = Sample::of(image, (0, image.width()), (0, image.height()))?;
+    Some(Entry {
+        id: id.to_owned(),
+        color: sample.mean,
+        signature: sample.signature(),
+    })
+}
+
+fn distance(a: Linear, b: Linear) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Converts `image` to a new `options.cols` x `options.rows` document drawn
+/// with `pack`, whose blocks are `blocks`. Mostly transparent cells are left
+/// empty.
+pub fn convert(
+    image: &RgbaImage,
+    blocks: &BlockSet,
+    options: &ConvertOptions,
+    pack: BlockPackRef,
+) -> Result<Document, ConvertError> {
+    let mut document = Document::new(options.cols, options.rows)?;
+    document.pack = pack;
+    let palette = match &options.blocks {
+        Some(ids) => Palette::with_ids(blocks, ids)?,
+        None => Palette::new(blocks),
+    };
+    if palette.is_empty() {
+        return Err(ConvertError::NoBlocks);
+    }
+    // Dithered cells are kept within the colours the palette spans, so the
+    // error from areas no block comes close to doesn't pile up.
+    let bounds = palette.bounds();
+    let spacing = palette.spacing();
+    let mut diffusion = Diffusion::new(options.dither, options.cols, options.rows);
+
+    for y in 0..options.rows {
+        let rows = span(y, options.rows, image.height());
+        for x in 0..options.cols {
+            let cols = span(x, options.cols, image.width());
+            let Some((cell, coverage)) = Sample::of(image, cols, rows) else {
+                continue;
+            };
+            if coverage < MIN_COVERAGE {
+                continue;
+            }
+            let cell = match options.dither {
+                Dither::None => cell,
+                Dither::Bayer => cell.shifted([bayer_threshold(x, y) * spacing; 3], bounds),
+                Dither::FloydSteinberg | Dither::Atkinson => {
+                    cell.shifted(diffusion.at(x, y), bounds)
+                }
+            };
+            let entry = palette
+                .nearest_entry(&cell.signature(), options.match_texture)
+                .expect("the palette is not empty");
+            diffusion.spread(x, y, std::array::from_fn(|i| cell.mean[i] - entry.color[i]));
+            if entry.id != document.pack.default_block {
+                document.grid.set(x, y, Some(&entry.id))?;
+            }
+        }
+    }
+    Ok(document)
+}
+
+/// The pixels covered by cell `index` of `count` along an axis `size` pixels
+/// long. Always at least one pixel wide, so pictures smaller than the grid
+/// still fill every cell.
+fn span(index: u32, count: u32, size: u32) -> (u32, u32) {
+    let edge = |i: u32| (u64::from(i) * u64::from(size) / u64::from(count)) as u32;
+    let start = edge(index);
+    (start, edge(index + 1).max(start + 1).min(size))
+}
+
+/// Splits a span in two; spans one pixel wide are used for both halves.
+fn halves((start, end): (u32, u32)) -> [(u32, u32); 2] {
+    let middle = start + (end - start) / 2;
+    if middle == start {
+        [(start, end); 2]
+    } else {
+        [(start, middle), (middle, end)]
+    }
+}
+
+/// Alpha-weighted average colour of a rectangle in linear light, and the
+/// fraction of it that is opaque.
+fn average(image: &RgbaImage, (x0, x1): (u32, u32), (y0, y1): (u32, u32)) -> Option<(Linear, f32)> {
+    static LINEAR: OnceLock<[f32; 256]> = OnceLock::new();
+    let linear = LINEAR.get_or_init(|| std::array::from_fn(|value| srgb_to_linear(value as u8)));
+
+    let mut sum = [0.0f32; 3];
+    let mut alpha = 0.0f32;
+    let mut pixels = 0u32;
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let [r, g, b, a] = image.get_pixel(x, y).0;
+            let a = f32::from(a) / 255.0;
+            for (sum, value) in sum.iter_mut().zip([r, g, b]) {
+                *sum += linear[usize::from(value)] * a;
+            }
+            alpha += a;
+            pixels += 1;
+        }
+    }
+    if alpha <= 0.0 {
+        return None;
+    }
+    Some((sum.map(|sum| sum / alpha), alpha / pixels as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+    use crate::blocks::Block;
+
+    const RED: Rgba<u8> = Rgba([220, 30, 30, 255]);
+    const BLUE: Rgba<u8> = Rgba([30, 30, 220, 255]);
+    /// About the same average in linear light as black beside light grey.
+    const GREY: Rgba<u8> = Rgba([147, 147, 147, 255]);
+
+    fn solid(color: Rgba<u8>) -> Block {
+        Block::still(RgbaImage::from_pixel(4, 4, color))
+    }
+
+    /// Black on the left half, white on the right.
+    fn split_block() -> Block {
+        Block::still(RgbaImage::from_fn(4, 4, |x, _| {
+            if x < 2 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 255])
+            }
+        }))
+    }
+
+    fn options(cols: u32, rows: u32, match_texture: bool) -> ConvertOptions {
+        ConvertOptions {
+            cols,
+            rows,
+            match_texture,
+            dither: Dither::None,
+            blocks: None,
+        }
+    }
+
+    fn black_and_white() -> BlockSet {
+        let mut blocks = BlockSet::default();
+        blocks.insert("black", solid(Rgba([0, 0, 0, 255])));
+        blocks.insert("white", solid(Rgba([255, 255, 255, 255])));
+        blocks
+    }
+
+    fn count(document: &Document, id: &str) -> usize {
+        document
+            .grid
+            .iter()
+            .filter(|&(_, _, cell)| cell == id)
+            .count()
+    }
+
+    #[test]
+    fn picks_nearest_block_per_cell() {
+        let mut blocks = BlockSet::default();
+        blocks.insert("red", solid(RED));
+        blocks.insert("blue", solid(BLUE));
+
+        // 4x2 picture: left half reddish, right half bluish.
+        let image = RgbaImage::from_fn(4, 2, |x, _| {
+            if x < 2 {
+                Rgba([200, 60, 40, 255])
+            } else {
+                Rgba([40, 60, 200, 255])
+            }
+        });
+        let document = convert(
+            &image,
+            &blocks,
+            &options(2, 1, false),
+            BlockPackRef::default(),
+        )
+        .unwrap();
+        assert_eq!(document.grid.get(0, 0).unwrap(), Some("red"));
+        assert_eq!(document.grid.get(1, 0).unwrap(), Some("blue"));
+
+        // Cells showing the pack's default block are left empty.
+        let pack = BlockPackRef {
+            id: "colours".into(),
+            default_block: "red".into(),
+        };
+        let document = convert(&image, &blocks, &options(2, 1, false), pack.clone()).unwrap();
+        assert_eq!(document.pack, pack);
+        assert_eq!(document.grid.get(0, 0).unwrap(), None);
+        assert_eq!(document.block_at(0, 0).unwrap(), "red");
+        assert_eq!(document.grid.get(1, 0).unwrap(), Some("blue"));
+    }
+
+    #[test]
+    fn texture_tells_apart_blocks_with_the_same_average() {
+        let mut blocks = BlockSet::default();
+        blocks.insert("grey", solid(GREY));
+        blocks.insert("split", split_block());
+
+        // Black beside light grey: the same average as the grey block, but
+        // laid out like the split one.
+        let image = RgbaImage::from_fn(4, 4, |x, _| {
+            if x < 2 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([200, 200, 200, 255])
+            }
+        });
+        let flat = convert(
+            &image,
+            &blocks,
+            &options(1, 1, false),
+            BlockPackRef::default(),
+        )
+        .unwrap();
+        let textured = convert(
+            &image,
+            &blocks,
+            &options(1, 1, true),
+            BlockPackRef::default(),
+        )
+        .unwrap();
+        assert_eq!(flat.grid.get(0, 0).unwrap(), Some("grey"));
+        assert_eq!(textured.grid.get(0, 0).unwrap(), Some("split"));
+    }
+
+    #[test]
+    fn leaves_transparent_cells_empty() {
+        let mut blocks = BlockSet::default();
+        blocks.insert("red", solid(RED));
+        blocks.insert("clear", Block::still(RgbaImage::new(4, 4)));
+
+        let image = RgbaImage::from_fn(2, 1, |x, _| if x == 0 { RED } else { Rgba([0; 4]) });
+        let document = convert(
+            &image,
+            &blocks,
+            &options(2, 1, true),
+            BlockPackRef::default(),
+        )
+        .unwrap();
+        assert_eq!(document.grid.get(0, 0).unwrap(), Some("red"));
+        assert_eq!(document.grid.get(1, 0).unwrap(), None);
+        assert_eq!(Palette::new(&blocks).len(), 1);
+    }
+
+    #[test]
+    fn stretches_small_pictures_over_the_grid() {
+        let mut blocks = BlockSet::default();
+        blocks.insert("red", solid(RED));
+        let image = RgbaImage::from_pixel(1, 1, RED);
+        let document = convert(
+            &image,
+            &blocks,
+            &options(3, 2, false),
+            BlockPackRef::default(),
+        )
+        .unwrap();
+        assert_eq!(document.grid.iter().count(), 6);
+        assert!(matches!(
+            convert(
+                &image,
+                &BlockSet::default(),
+                &options(3, 2, false),
+                BlockPackRef::default()
+            ),
+            Err(ConvertError::NoBlocks)
+        ));
+    }
+
+    #[test]
+    fn dithering_mixes_blocks_to_match_the_average() {
+        // Half as bright as white in linear light.
+        let image = RgbaImage::from_pixel(8, 8, Rgba([188, 188, 188, 255]));
+        let blocks = black_and_white();
+        let flat = convert(
+            &image,
+            &blocks,
+            &options(8, 8, false),
+            BlockPackRef::default(),
+        )
+        .unwrap();
+        assert_eq!(count(&flat, "white"), 64);
+
+        for dither in [Dither::FloydSteinberg, Dither::Atkinson, Dither::Bayer] {
+            let options = ConvertOptions {
+                dither,
+                ..options(8, 8, false)
+            };
+            let document = convert(&image, &blocks, &options, BlockPackRef::default()).unwrap();
+            // Atkinson drops some error, so only ask for a real mix.
+            let white = count(&document, "white");
+            assert!((16..=48).contains(&white), "{dither:?}: {white} white");
+        }
+    }
+
+    #[test]
+    fn picks_only_from_the_chosen_blocks() {
+        let mut blocks = black_and_white();
+        blocks.insert("red", solid(RED));
+        let image = RgbaImage::from_pixel(2, 2, RED);
+        let options = ConvertOptions {
+            blocks: Some(vec!["black".into(), "white".into()]),
+            ..options(1, 1, false)
+        };
+        let document = convert(&image, &blocks, &options, BlockPackRef::default()).unwrap();
+        assert_ne!(document.grid.get(0, 0).unwrap(), Some("red"));
+
+        let options = ConvertOptions {
+            blocks: Some(vec!["black".into(), "blue".into()]),
+            ..options
+        };
+        assert_eq!(
+            convert(&image, &blocks, &options, BlockPackRef::default()).unwrap_err(),
+            ConvertError::UnknownBlock("blue".into())
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Dithering for image conversion. Error diffusion passes the difference
+//! between each cell and the block picked for it on to the cells not yet
+//! converted; ordered dithering nudges every cell by a fixed threshold
+//! pattern instead, so neighbouring cells never affect each other.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum Dither {
+    /// Every cell gets its nearest block.
+    #[default]
+    None,
+    /// Error diffusion over four neighbours; smooth, but can streak.
+    FloydSteinberg,
+    /// Error diffusion that drops a quarter of the error, keeping more
+    /// contrast at the cost of detail in very dark and light areas.
+    Atkinson,
+    /// A repeating 4x4 threshold pattern.
+    Bayer,
+}
+
+/// Where a cell's error goes: `(dx, dy, share)` for each neighbour.
+type Kernel = [(i32, u32, f32)];
+
+const FLOYD_STEINBERG: [(i32, u32, f32); 4] = [
+    (1, 0, 7.0 / 16.0),
+    (-1, 1, 3.0 / 16.0),
+    (0, 1, 5.0 / 16.0),
+    (1, 1, 1.0 / 16.0),
+];
+
+const ATKINSON: [(i32, u32, f32); 6] = [
+    (1, 0, 1.0 / 8.0),
+    (2, 0, 1.0 / 8.0),
+    (-1, 1, 1.0 / 8.0),
+    (0, 1, 1.0 / 8.0),
+    (1, 1, 1.0 / 8.0),
+    (0, 2, 1.0 / 8.0),
+];
+
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl Dither {
+    fn kernel(self) -> &'static Kernel {
+        match self {
+            Dither::FloydSteinberg => &FLOYD_STEINBERG,
+            Dither::Atkinson => &ATKINSON,
+            Dither::None | Dither::Bayer => &[],
+        }
+    }
+}
+
+/// Bayer threshold for cell `x`, `y`, spread evenly over `-0.5..0.5`.
+pub fn bayer_threshold(x: u32, y: u32) -> f32 {
+    (f32::from(BAYER[y as usize % 4][x as usize % 4]) + 0.5) / 16.0 - 0.5
+}
+
+/// Error carried into each cell of a grid, in linear light.
+#[derive(Debug, Clone)]
+pub struct Diffusion {
+    kernel: &'static Kernel,
+    cols: u32,
+    rows: u32,
+    error: Vec<[f32; 3]>,
+}
+
+impl Diffusion {
+    pub fn new(dither: Dither, cols: u32, rows: u32) -> Self {
+        let kernel = dither.kernel();
+        let cells = if kernel.is_empty() {
+            0
+        } else {
+            cols as usize * rows as usize
+        };
+        Self {
+            kernel,
+            cols,
+            rows,
+            error: vec![[0.0; 3]; cells],
+        }
+    }
+
+    /// Error carried into cell `x`, `y` so far.
+    pub fn at(&self, x: u32, y: u32) -> [f32; 3] {
+        self.index(x, y).map_or([0.0; 3], |index| self.error[index])
+    }
+
+    /// Passes `error` from cell `x`, `y` on to the neighbours the kernel
+    /// names; shares that would fall outside the grid are dropped.
+    pub fn spread(&mut self, x: u32, y: u32, error: [f32; 3]) {
+        for &(dx, dy, share) in self.kernel {
+            let Some(nx) = x.checked_add_signed(dx) else {
+                continue;
+            };
+            if let Some(index) = self.index(nx, y + dy) {
+                for (cell, error) in self.error[index].iter_mut().zip(error) {
+                    *cell += error * share;
+                }
+            }
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> Option<usize> {
+        (!self.error.is_empty() && x < self.cols && y < self.rows)
+            .then(|| y as usize * self.cols as usize + x as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernels_and_thresholds_are_balanced() {
+        let total = |kernel: &Kernel| kernel.iter().map(|&(_, _, share)| share).sum::<f32>();
+        assert_eq!(total(&FLOYD_STEINBERG), 1.0);
+        assert_eq!(total(&ATKINSON), 0.75);
+
+        let thresholds: Vec<f32> = (0..4)
+            .flat_map(|y| (0..4).map(move |x| bayer_threshold(x, y)))
+            .collect();
+        assert_eq!(thresholds.iter().sum::<f32>(), 0.0);
+        assert!(thresholds.iter().all(|t| (-0.5..0.5).contains(t)));
+        assert_eq!(bayer_threshold(5, 6), bayer_threshold(1, 2));
+    }
+}
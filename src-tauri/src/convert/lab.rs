@@ -0,0 +1,78 @@
+//! CIELAB colours, where straight-line distance roughly tracks how different
+//! two colours look. Conversions assume sRGB with a D65 white point.
+
+/// D65 reference white in XYZ.
+const WHITE: [f32; 3] = [0.950_47, 1.0, 1.088_83];
+
+const EPSILON: f32 = 216.0 / 24_389.0;
+const KAPPA: f32 = 24_389.0 / 27.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Lab {
+    pub fn from_srgb(rgb: [u8; 3]) -> Self {
+        Self::from_linear(rgb.map(srgb_to_linear))
+    }
+
+    /// Converts linear-light RGB with channels in `0.0..=1.0`.
+    pub fn from_linear([r, g, b]: [f32; 3]) -> Self {
+        let xyz = [
+            0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b,
+            0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b,
+            0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b,
+        ];
+        let [fx, fy, fz] = [0, 1, 2].map(|i| {
+            let t = xyz[i] / WHITE[i];
+            if t > EPSILON {
+                t.cbrt()
+            } else {
+                (KAPPA * t + 16.0) / 116.0
+            }
+        });
+        Self {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    /// Squared CIE76 colour difference.
+    pub fn distance_squared(self, other: Lab) -> f32 {
+        (self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)
+    }
+}
+
+pub fn srgb_to_linear(value: u8) -> f32 {
+    let c = f32::from(value) / 255.0;
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(lab: Lab, expected: [f32; 3]) {
+        let [l, a, b] = expected;
+        assert!(
+            (lab.l - l).abs() < 0.05 && (lab.a - a).abs() < 0.05 && (lab.b - b).abs() < 0.05,
+            "{lab:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn converts_reference_colours() {
+        assert_close(Lab::from_srgb([255, 255, 255]), [100.0, 0.0, 0.0]);
+        assert_close(Lab::from_srgb([0, 0, 0]), [0.0, 0.0, 0.0]);
+        assert_close(Lab::from_srgb([255, 0, 0]), [53.24, 80.09, 67.20]);
+        assert_close(Lab::from_srgb([0, 0, 255]), [32.30, 79.19, -107.86]);
+    }
+}
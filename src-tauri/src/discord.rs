@@ -0,0 +1,489 @@
+//! Discord emoji text. Every cell becomes one emoji, `:blockId:` unless an
+//! emoji profile says otherwise, split into messages by the rules in
+//! [`crate::text`].
+//!
+//! Text can also be read back: pasted messages are parsed into rows of emoji
+//! and mapped to block ids through the same profile.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::blocks::BlockSet;
+use crate::emoji::{Emoji, EmojiProfile};
+use crate::grid::{BlockPackRef, Document, GridError};
+use crate::text::{self, Length, LongRows, MessageLimits, TextError, TextExporter};
+
+/// Message limit for regular Discord accounts.
+pub const DEFAULT_CHAR_LIMIT: usize = 2000;
+
+/// Message limit with Discord Nitro.
+pub const NITRO_CHAR_LIMIT: usize = 4000;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DiscordError {
+    #[error(transparent)]
+    Text(#[from] TextError),
+    #[error("line {line} mixes emoji with other text at {text:?}")]
+    UnexpectedText { line: usize, text: String },
+    #[error("no emoji found in the text")]
+    NoEmoji,
+    #[error(transparent)]
+    Grid(#[from] GridError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "../../src/bindings/", optional_fields)]
+pub struct DiscordOptions {
+    /// Maximum characters per message.
+    pub char_limit: usize,
+    pub long_rows: LongRows,
+}
+
+impl Default for DiscordOptions {
+    fn default() -> Self {
+        Self {
+            char_limit: DEFAULT_CHAR_LIMIT,
+            long_rows: LongRows::default(),
+        }
+    }
+}
+
+impl DiscordOptions {
+    pub fn limits(&self) -> MessageLimits {
+        MessageLimits {
+            max_length: self.char_limit,
+            length: Length::Chars,
+            line_break: "\n",
+            long_rows: self.long_rows,
+        }
+    }
+}
+
+/// Posts to Discord, through an emoji profile if one is picked.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Discord<'a> {
+    pub profile: Option<&'a EmojiProfile>,
+    pub options: DiscordOptions,
+}
+
+impl TextExporter for Discord<'_> {
+    fn token(&self, block_id: &str) -> String {
+        match self.profile {
+            Some(profile) => profile.token(block_id),
+            None => format!(":{block_id}:"),
+        }
+    }
+
+    fn limits(&self) -> MessageLimits {
+        self.options.limits()
+    }
+}
+
+/// A document read back from emoji text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedText {
+    pub document: Document,
+    /// Emoji that matched no block, as written and sorted. Their cells are
+    /// left empty.
+    pub unknown: Vec<String>,
+}
+
+/// Emoji text for a document, kept as one token per cell so rows can be
+/// wrapped without splitting an emoji.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmojiText {
+    rows: Vec<Vec<String>>,
+}
+
+impl EmojiText {
+    pub fn new(rows: Vec<Vec<String>>) -> Self {
+        Self { rows }
+    }
+
+    /// Converts `document` to `:blockId:` emoji. Empty cells use the pack's
+    /// default block.
+    pub fn from_document(document: &Document) -> Self {
+        Self::map_cells(document, |id| format!(":{id}:"))
+    }
+
+    /// Converts `document` using the emoji in `profile`, falling back to
+    /// `:blockId:` for blocks it doesn't map.
+    pub fn with_profile(document: &Document, profile: &EmojiProfile) -> Self {
+        Self::map_cells(document, |id| profile.token(id))
+    }
+
+    fn map_cells(document: &Document, token: impl Fn(&str) -> String) -> Self {
+        Self {
+            rows: text::rows(document, token),
+        }
+    }
+
+    /// Parses pasted messages, one row per line. Lines without any emoji,
+    /// such as blank lines between messages or author names, are skipped; a
+    /// line mixing emoji with other text is an error. Rows wrapped onto
+    /// several lines on export can't be told apart from real rows and come
+    /// back as separate rows.
+    pub fn parse(text: &str) -> Result<Self, DiscordError> {
+        let mut rows = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let mut tokens = Vec::new();
+            let mut rest = line.trim_start();
+            while !rest.is_empty() {
+                let Some(token) = leading_emoji(rest) else {
+                    if tokens.is_empty() {
+                        break;
+                    }
+                    return Err(DiscordError::UnexpectedText {
+                        line: index + 1,
+                        text: rest.split_whitespace().next().unwrap_or(rest).to_owned(),
+                    });
+                };
+                tokens.push(token.to_owned());
+                rest = rest[token.len()..].trim_start();
+            }
+            if !tokens.is_empty() {
+                rows.push(tokens);
+            }
+        }
+        if rows.is_empty() {
+            return Err(DiscordError::NoEmoji);
+        }
+        Ok(Self { rows })
+    }
+
+    /// Builds a document drawn with `pack`, whose blocks are `blocks`, as
+    /// wide as the longest row, padding shorter rows with empty cells. Each
+    /// emoji is mapped back through `profile` first, then its name is taken
+    /// as a block id if `blocks` has one by that name. Block ids needn't be
+    /// valid emoji names, as `:blockId:` is what's exported for blocks the
+    /// profile doesn't map.
+    pub fn to_document(
+        &self,
+        profile: Option<&EmojiProfile>,
+        blocks: &BlockSet,
+        pack: BlockPackRef,
+    ) -> Result<ImportedText, DiscordError> {
+        let cols = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut document = Document::new(cols as u32, self.rows.len() as u32)?;
+        document.pack = pack;
+        let mut unknown = BTreeSet::new();
+
+        for (y, tokens) in self.rows.iter().enumerate() {
+            for (x, token) in tokens.iter().enumerate() {
+                let block_id = token
+                    .parse::<Emoji>()
+                    .ok()
+                    .and_then(|emoji| profile?.block_for(&emoji))
+                    .or_else(|| {
+                        let name = emoji_name(token);
+                        blocks.get(name).map(|_| name)
+                    });
+                let Some(block_id) = block_id else {
+                    unknown.insert(token.clone());
+                    continue;
+                };
+                if block_id != document.pack.default_block {
+                    document.grid.set(x as u32, y as u32, Some(block_id))?;
+                }
+            }
+        }
+
+        Ok(ImportedText {
+            document,
+            unknown: unknown.into_iter().collect(),
+        })
+    }
+
+    pub fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+
+    /// Splits the text into messages of at most `options.char_limit`
+    /// characters, breaking only between rows unless a row has to wrap.
+    pub fn chunks(&self, options: &DiscordOptions) -> Result<Vec<String>, TextError> {
+        text::split_messages(&self.rows, &options.limits())
+    }
+}
+
+/// One line per row, with no message limit applied.
+impl std::fmt::Display for EmojiText {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, tokens) in self.rows.iter().enumerate() {
+            if index > 0 {
+                f.write_str("\n")?;
+            }
+            for token in tokens {
+                f.write_str(token)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `:name:` or `<:name:id>` emoji at the start of `text`, if any. A
+/// `:name:` is anything without whitespace between the colons, since it may
+/// be a block id rather than a real emoji.
+fn leading_emoji(text: &str) -> Option<&str> {
+    match text.as_bytes().first()? {
+        b'<' => {
+            let token = &text[..text.find('>')? + 1];
+            token.parse::<Emoji>().is_ok().then_some(token)
+        }
+        b':' => {
+            let end = text[1..].find(':')? + 1;
+            let name = &text[1..end];
+            (!name.is_empty() && !name.contains(char::is_whitespace)).then_some(&text[..=end])
+        }
+        _ => None,
+    }
+}
+
+/// The name in a token found by `leading_emoji`.
+fn emoji_name(token: &str) -> &str {
+    match token.strip_prefix('<') {
+        Some(custom) => custom
+            .trim_start_matches('a')
+            .split(':')
+            .nth(1)
+            .unwrap_or_default(),
+        None => token.trim_matches(':'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbaImage;
+
+    use super::*;
+    use crate::blocks::Block;
+
+    fn text(rows: &[&[&str]]) -> EmojiText {
+        EmojiText::new(
+            rows.iter()
+                .map(|row| row.iter().map(|token| token.to_string()).collect())
+                .collect(),
+        )
+    }
+
+    fn options(char_limit: usize, long_rows: LongRows) -> DiscordOptions {
+        DiscordOptions {
+            char_limit,
+            long_rows,
+        }
+    }
+
+    #[test]
+    fn emits_one_line_per_row() {
+        let mut document = Document::new(2, 2).unwrap();
+        document.grid.set(0, 1, Some("03")).unwrap();
+        assert_eq!(
+            EmojiText::from_document(&document).to_string(),
+            ":12::12:\n:03::12:"
+        );
+    }
+
+    #[test]
+    fn applies_emoji_profile() {
+        let mut profile = EmojiProfile::default();
+        profile
+            .emoji
+            .insert("12".into(), "<a:blank:9>".parse().unwrap());
+        let mut document = Document::new(2, 1).unwrap();
+        document.grid.set(1, 0, Some("03")).unwrap();
+        assert_eq!(
+            EmojiText::with_profile(&document, &profile).to_string(),
+            "<a:blank:9>:03:"
+        );
+    }
+
+    #[test]
+    fn keeps_short_text_in_one_message() {
+        let text = text(&[&["aa", "aa"], &["bb", "bb"]]);
+        assert_eq!(
+            text.chunks(&DiscordOptions::default()).unwrap(),
+            vec!["aaaa\nbbbb"]
+        );
+    }
+
+    #[test]
+    fn splits_between_rows() {
+        let text = text(&[&["aaaa"], &["bbbb"], &["cccc"]]);
+        assert_eq!(
+            text.chunks(&options(9, LongRows::Error)).unwrap(),
+            vec!["aaaa\nbbbb", "cccc"]
+        );
+        // Exactly at the limit still fits.
+        assert_eq!(
+            text.chunks(&options(14, LongRows::Error)).unwrap(),
+            vec!["aaaa\nbbbb\ncccc"]
+        );
+    }
+
+    #[test]
+    fn reports_rows_over_the_limit() {
+        let text = text(&[&["aa"], &["bb", "bb", "bb"]]);
+        assert_eq!(
+            text.chunks(&options(5, LongRows::Error)),
+            Err(TextError::RowTooLong {
+                row: 2,
+                length: 6,
+                limit: 5
+            })
+        );
+    }
+
+    #[test]
+    fn wraps_long_rows_between_emoji() {
+        let text = text(&[&["aa"], &["bb", "bb", "bb"]]);
+        assert_eq!(
+            text.chunks(&options(5, LongRows::Wrap)).unwrap(),
+            vec!["aa", "bbbb", "bb"]
+        );
+        assert_eq!(
+            text.chunks(&options(1, LongRows::Wrap)),
+            Err(TextError::EmojiTooLong {
+                emoji: "aa".to_owned(),
+                limit: 1
+            })
+        );
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        let text = text(&[&["é", "é"], &["é", "é"]]);
+        assert_eq!(
+            text.chunks(&options(5, LongRows::Error)).unwrap(),
+            vec!["éé\néé"]
+        );
+    }
+
+    #[test]
+    fn wide_grid_fits_nitro_limit_only() {
+        let id = "a_block_with_a_rather_long_descriptive_id";
+        let mut document = Document::new(64, 2).unwrap();
+        for y in 0..2 {
+            for x in 0..64 {
+                document.grid.set(x, y, Some(id)).unwrap();
+            }
+        }
+        let text = EmojiText::from_document(&document);
+        assert!(matches!(
+            text.chunks(&options(DEFAULT_CHAR_LIMIT, LongRows::Error)),
+            Err(TextError::RowTooLong { row: 1, .. })
+        ));
+        let chunks = text
+            .chunks(&options(NITRO_CHAR_LIMIT, LongRows::Error))
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+    }
+
+    fn blocks(ids: &[&str]) -> BlockSet {
+        let mut blocks = BlockSet::default();
+        for id in ids {
+            blocks.insert(*id, Block::still(RgbaImage::new(1, 1)));
+        }
+        blocks
+    }
+
+    #[test]
+    fn parses_concatenated_messages() {
+        let pasted = "jack — Today at 12:04\n:03: :12:<:blank:1>\n\n:12:\n";
+        let text = EmojiText::parse(pasted).unwrap();
+        assert_eq!(
+            text.rows(),
+            &[
+                vec![
+                    ":03:".to_owned(),
+                    ":12:".to_owned(),
+                    "<:blank:1>".to_owned()
+                ],
+                vec![":12:".to_owned()],
+            ]
+        );
+        assert_eq!(
+            EmojiText::parse(":03: hello"),
+            Err(DiscordError::UnexpectedText {
+                line: 1,
+                text: "hello".to_owned()
+            })
+        );
+        assert_eq!(EmojiText::parse("no art here"), Err(DiscordError::NoEmoji));
+    }
+
+    #[test]
+    fn imports_through_profile_and_reports_unknown() {
+        let mut profile = EmojiProfile::default();
+        profile
+            .emoji
+            .insert("col_blue_hi".into(), "<:blue:77>".parse().unwrap());
+        let text = EmojiText::parse("<:renamed:77>:03:\n:mystery::12::mystery:").unwrap();
+        let imported = text
+            .to_document(
+                Some(&profile),
+                &blocks(&["03", "12"]),
+                BlockPackRef::default(),
+            )
+            .unwrap();
+
+        let grid = &imported.document.grid;
+        assert_eq!((grid.cols(), grid.rows()), (3, 2));
+        assert_eq!(grid.get(0, 0).unwrap(), Some("col_blue_hi"));
+        assert_eq!(grid.get(1, 0).unwrap(), Some("03"));
+        assert_eq!(grid.get(2, 0).unwrap(), None);
+        assert_eq!(grid.get(1, 1).unwrap(), None);
+        assert_eq!(imported.unknown, vec![":mystery:"]);
+    }
+
+    #[test]
+    fn round_trips_exported_text() {
+        let mut document = Document::new(3, 2).unwrap();
+        document.grid.set(0, 0, Some("03")).unwrap();
+        document.grid.set(2, 1, Some("05")).unwrap();
+        let exported = EmojiText::from_document(&document).to_string();
+        let imported = EmojiText::parse(&exported)
+            .unwrap()
+            .to_document(None, &blocks(&["03", "05", "12"]), BlockPackRef::default())
+            .unwrap();
+        assert_eq!(imported.document, document);
+        assert!(imported.unknown.is_empty());
+    }
+
+    #[test]
+    fn round_trips_block_ids_that_are_not_emoji_names() {
+        let ids = ["12", "Horizontal_164x64~1", "col-blue"];
+        let mut document = Document::new(3, 2).unwrap();
+        document.grid.set(0, 0, Some(ids[1])).unwrap();
+        document.grid.set(1, 1, Some(ids[1])).unwrap();
+        document.grid.set(2, 1, Some(ids[2])).unwrap();
+        let mut profile = EmojiProfile::default();
+        profile
+            .emoji
+            .insert("12".into(), "<:blank:1>".parse().unwrap());
+
+        let exported = EmojiText::with_profile(&document, &profile).to_string();
+        let imported = EmojiText::parse(&exported)
+            .unwrap()
+            .to_document(Some(&profile), &blocks(&ids), BlockPackRef::default())
+            .unwrap();
+        assert_eq!(imported.document, document);
+        assert!(imported.unknown.is_empty());
+    }
+
+    #[test]
+    fn imports_into_the_given_pack() {
+        let pack = BlockPackRef {
+            id: "og".into(),
+            default_block: "03".into(),
+        };
+        let imported = EmojiText::parse(":03::12:")
+            .unwrap()
+            .to_document(None, &blocks(&["03", "12"]), pack.clone())
+            .unwrap();
+        assert_eq!(imported.document.pack, pack);
+        assert_eq!(imported.document.grid.get(0, 0).unwrap(), None);
+        assert_eq!(imported.document.grid.get(1, 0).unwrap(), Some("12"));
+    }
+}
@@ -0,0 +1,322 @@
+//! Per-server emoji profiles. Block ids are file stems like `12` or
+//! `col_blue_hi`, but a server's custom emoji can be named anything, so each
+//! profile maps block ids to the emoji to post for them. Blocks a profile
+//! doesn't mention fall back to `:blockId:`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::grid::Document;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EmojiError {
+    #[error("failed to access emoji profiles: {0}")]
+    Io(#[from] io::Error),
+    #[error("emoji profiles file is not valid: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0:?} is not a valid emoji; use a name like blank, <:blank:123> or <a:blank:123>")]
+    InvalidEmoji(String),
+    #[error("profile name must not be empty")]
+    EmptyProfileName,
+    #[error("no emoji profile named {0:?}")]
+    UnknownProfile(String),
+}
+
+/// The emoji posted for a block: a bare name, written `:name:`, or a custom
+/// emoji with its id, written `<:name:id>` (`<a:name:id>` when animated).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Emoji {
+    Name(String),
+    Custom {
+        name: String,
+        id: u64,
+        animated: bool,
+    },
+}
+
+impl Emoji {
+    pub fn name(&self) -> &str {
+        match self {
+            Emoji::Name(name) | Emoji::Custom { name, .. } => name,
+        }
+    }
+}
+
+/// Discord emoji names are 2 to 32 letters, digits or underscores.
+fn is_valid_name(name: &str) -> bool {
+    (2..=32).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl FromStr for Emoji {
+    type Err = EmojiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || EmojiError::InvalidEmoji(s.to_owned());
+        let s = s.trim();
+
+        let Some(inner) = s.strip_prefix('<').and_then(|s| s.strip_suffix('>')) else {
+            let name = s
+                .strip_prefix(':')
+                .and_then(|s| s.strip_suffix(':'))
+                .unwrap_or(s);
+            return is_valid_name(name)
+                .then(|| Emoji::Name(name.to_owned()))
+                .ok_or_else(err);
+        };
+
+        let (animated, rest) = match inner.strip_prefix("a:") {
+            Some(rest) => (true, rest),
+            None => (false, inner.strip_prefix(':').ok_or_else(err)?),
+        };
+        let (name, id) = rest.split_once(':').ok_or_else(err)?;
+        if !is_valid_name(name) || !id.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(err());
+        }
+        Ok(Emoji::Custom {
+            name: name.to_owned(),
+            id: id.parse().map_err(|_| err())?,
+            animated,
+        })
+    }
+}
+
+impl TryFrom<String> for Emoji {
+    type Error = EmojiError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Emoji> for String {
+    fn from(emoji: Emoji) -> Self {
+        emoji.to_string()
+    }
+}
+
+/// Formats the emoji exactly as it's posted in a message.
+impl fmt::Display for Emoji {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Emoji::Name(name) => write!(f, ":{name}:"),
+            Emoji::Custom {
+                name,
+                id,
+                animated: false,
+            } => write!(f, "<:{name}:{id}>"),
+            Emoji::Custom {
+                name,
+                id,
+                animated: true,
+            } => write!(f, "<a:{name}:{id}>"),
+        }
+    }
+}
+
+/// Emoji to use for each block when posting to one server.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct EmojiProfile {
+    #[ts(as = "BTreeMap<String, String>")]
+    pub emoji: BTreeMap<String, Emoji>,
+}
+
+impl EmojiProfile {
+    /// The text posted for `block_id`.
+    pub fn token(&self, block_id: &str) -> String {
+        match self.emoji.get(block_id) {
+            Some(emoji) => emoji.to_string(),
+            None => format!(":{block_id}:"),
+        }
+    }
+
+    /// The block posted as `emoji`. Custom emoji match on id when both sides
+    /// have one, since the same name can differ between servers; otherwise
+    /// they match on name.
+    pub fn block_for(&self, emoji: &Emoji) -> Option<&str> {
+        if let Emoji::Custom { id, .. } = emoji {
+            let by_id = self.emoji.iter().find(|(_, mapped)| {
+                matches!(mapped, Emoji::Custom { id: mapped_id, .. } if mapped_id == id)
+            });
+            if let Some((block_id, _)) = by_id {
+                return Some(block_id);
+            }
+        }
+        self.emoji
+            .iter()
+            .find(|(_, mapped)| mapped.name() == emoji.name())
+            .map(|(block_id, _)| block_id.as_str())
+    }
+
+    /// Blocks used in `document` that this profile has no emoji for, sorted.
+    pub fn unmapped(&self, document: &Document) -> Vec<String> {
+        let grid = &document.grid;
+        let mut unmapped = BTreeSet::new();
+        for y in 0..grid.rows() {
+            for x in 0..grid.cols() {
+                let id = document
+                    .block_at(x, y)
+                    .expect("coordinates are within the grid");
+                if !self.emoji.contains_key(id) {
+                    unmapped.insert(id);
+                }
+            }
+        }
+        unmapped.into_iter().map(str::to_owned).collect()
+    }
+}
+
+/// Every saved profile and the one picked for export.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/", optional_fields)]
+pub struct EmojiProfiles {
+    #[serde(default)]
+    pub active: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, EmojiProfile>,
+}
+
+impl EmojiProfiles {
+    /// Reads profiles from `path`; a missing file means no profiles yet.
+    pub fn load(path: &Path) -> Result<Self, EmojiError> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), EmojiError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn active_profile(&self) -> Option<&EmojiProfile> {
+        self.active
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+    }
+
+    /// Adds or replaces the profile called `name`.
+    pub fn insert(&mut self, name: &str, profile: EmojiProfile) -> Result<(), EmojiError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(EmojiError::EmptyProfileName);
+        }
+        self.profiles.insert(name.to_owned(), profile);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.profiles.remove(name);
+        if self.active.as_deref() == Some(name) {
+            self.active = None;
+        }
+    }
+
+    /// Picks the profile used for export, or none for plain `:blockId:`.
+    pub fn set_active(&mut self, name: Option<String>) -> Result<(), EmojiError> {
+        if let Some(name) = &name {
+            if !self.profiles.contains_key(name) {
+                return Err(EmojiError::UnknownProfile(name.clone()));
+            }
+        }
+        self.active = name;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_and_custom_emoji() {
+        assert_eq!(
+            "blank".parse::<Emoji>().unwrap(),
+            Emoji::Name("blank".into())
+        );
+        assert_eq!(
+            ":blank:".parse::<Emoji>().unwrap(),
+            Emoji::Name("blank".into())
+        );
+        assert_eq!(
+            "<:blue_hi:112233445566778899>".parse::<Emoji>().unwrap(),
+            Emoji::Custom {
+                name: "blue_hi".into(),
+                id: 112233445566778899,
+                animated: false
+            }
+        );
+        let animated: Emoji = "<a:hand:42>".parse().unwrap();
+        assert_eq!(animated.to_string(), "<a:hand:42>");
+        assert_eq!(animated.name(), "hand");
+
+        for bad in [
+            "",
+            "x",
+            "has space",
+            "<:blue:>",
+            "<:blue:12a>",
+            "<b:blue:1>",
+            "<:b:1>",
+        ] {
+            assert!(bad.parse::<Emoji>().is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn falls_back_to_block_id_and_reports_unmapped() {
+        let mut profile = EmojiProfile::default();
+        profile
+            .emoji
+            .insert("12".into(), "<:blank:1>".parse().unwrap());
+
+        let mut document = Document::new(2, 1).unwrap();
+        document.grid.set(1, 0, Some("col_blue_hi")).unwrap();
+
+        assert_eq!(profile.token("12"), "<:blank:1>");
+        assert_eq!(profile.token("col_blue_hi"), ":col_blue_hi:");
+        assert_eq!(profile.unmapped(&document), vec!["col_blue_hi"]);
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let json =
+            r#"{"active":"home","profiles":{"home":{"emoji":{"12":"<a:b12:7>","05":":five:"}}}}"#;
+        let profiles: EmojiProfiles = serde_json::from_str(json).unwrap();
+        assert_eq!(profiles.active_profile().unwrap().token("05"), ":five:");
+
+        let reparsed: EmojiProfiles =
+            serde_json::from_slice(&serde_json::to_vec(&profiles).unwrap()).unwrap();
+        assert_eq!(reparsed, profiles);
+
+        let bad = r#"{"profiles":{"home":{"emoji":{"12":"not an emoji"}}}}"#;
+        assert!(serde_json::from_str::<EmojiProfiles>(bad).is_err());
+    }
+
+    #[test]
+    fn active_profile_must_exist() {
+        let mut profiles = EmojiProfiles::default();
+        profiles.insert("home", EmojiProfile::default()).unwrap();
+        assert!(profiles.set_active(Some("away".into())).is_err());
+        profiles.set_active(Some("home".into())).unwrap();
+        profiles.remove("home");
+        assert_eq!(profiles.active, None);
+        assert!(matches!(
+            profiles.insert("  ", EmojiProfile::default()),
+            Err(EmojiError::EmptyProfileName)
+        ));
+    }
+}
@@ -0,0 +1,53 @@
+use std::io;
+
+use serde::{Serialize, Serializer};
+
+use crate::convert::ConvertError;
+use crate::discord::DiscordError;
+use crate::emoji::EmojiError;
+use crate::grid::GridError;
+use crate::packs::PackError;
+use crate::project::ProjectError;
+use crate::render::animation::AnimationError;
+use crate::render::RenderError;
+use crate::text::TextError;
+
+/// Error returned from Tauri commands. Serialized as its message so the
+/// frontend receives a readable string when an `invoke` rejects.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Grid(#[from] GridError),
+    #[error(transparent)]
+    Project(#[from] ProjectError),
+    #[error(transparent)]
+    Discord(#[from] DiscordError),
+    #[error(transparent)]
+    Text(#[from] TextError),
+    #[error(transparent)]
+    Emoji(#[from] EmojiError),
+    #[error(transparent)]
+    Convert(#[from] ConvertError),
+    #[error(transparent)]
+    Pack(#[from] PackError),
+    #[error(transparent)]
+    Render(#[from] RenderError),
+    #[error(transparent)]
+    Animation(#[from] AnimationError),
+    #[error(transparent)]
+    Watch(#[from] notify::Error),
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
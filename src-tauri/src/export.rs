@@ -0,0 +1,189 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+use image::ImageFormat;
+use serde::Serialize;
+use tauri::State;
+
+use crate::discord::{DiscordOptions, EmojiText};
+use crate::emoji::{EmojiProfile, EmojiProfiles};
+use crate::error::Result;
+use crate::render::animation::{self, AnimationFormat};
+use crate::render::svg;
+use crate::render::{self, RenderOptions};
+use crate::state::{BlockState, DocumentState, EmojiProfileState};
+use crate::text::matrix::Matrix;
+use crate::text::slack::Slack;
+use crate::text::unicode::Unicode;
+use crate::text::{TextExporter, TextTarget};
+
+/// Renders the open document and writes it to `path` as a PNG.
+#[tauri::command]
+pub fn render_png(
+    path: PathBuf,
+    options: RenderOptions,
+    documents: State<'_, DocumentState>,
+    blocks: State<'_, BlockState>,
+) -> Result<()> {
+    let image = {
+        let current = documents.lock();
+        let document = &current.project.document;
+        render::render(document, blocks.lock().blocks(&document.pack.id)?, &options)?
+    };
+    image.save_with_format(&path, ImageFormat::Png)?;
+    Ok(())
+}
+
+/// Renders one full loop of the open document's animated blocks and writes it
+/// to `path` as a GIF or APNG. Returns the number of frames written.
+#[tauri::command]
+pub fn export_animation(
+    path: PathBuf,
+    format: AnimationFormat,
+    options: RenderOptions,
+    documents: State<'_, DocumentState>,
+    blocks: State<'_, BlockState>,
+) -> Result<usize> {
+    let document = documents.lock().project.document.clone();
+    let writer = BufWriter::new(File::create(&path)?);
+    let library = blocks.lock();
+    let blocks = library.blocks(&document.pack.id)?;
+    let timeline = animation::encode(&document, blocks, &options, format, writer)?;
+    Ok(timeline.len())
+}
+
+/// Writes the open document to `path` as an SVG with the blocks embedded.
+#[tauri::command]
+pub fn export_svg(
+    path: PathBuf,
+    options: RenderOptions,
+    documents: State<'_, DocumentState>,
+    blocks: State<'_, BlockState>,
+) -> Result<()> {
+    let svg = {
+        let current = documents.lock();
+        let document = &current.project.document;
+        svg::render_svg(document, blocks.lock().blocks(&document.pack.id)?, &options)?
+    };
+    fs::write(&path, svg)?;
+    Ok(())
+}
+
+/// Discord messages for the open document and the blocks the active emoji
+/// profile has no emoji for.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscordMessages {
+    messages: Vec<String>,
+    unmapped: Vec<String>,
+}
+
+/// The open document as Discord emoji text using the active emoji profile,
+/// split into messages.
+#[tauri::command]
+pub fn discord_messages(
+    options: DiscordOptions,
+    documents: State<'_, DocumentState>,
+    profiles: State<'_, EmojiProfileState>,
+) -> Result<DiscordMessages> {
+    let profiles = profiles.get();
+    let current = documents.lock();
+    let document = &current.project.document;
+
+    let (text, unmapped) = match profiles.active_profile() {
+        Some(profile) => (
+            EmojiText::with_profile(document, profile),
+            profile.unmapped(document),
+        ),
+        None => (EmojiText::from_document(document), Vec::new()),
+    };
+    Ok(DiscordMessages {
+        messages: text.chunks(&options)?,
+        unmapped,
+    })
+}
+
+/// Writes the open document's Discord emoji text to `path` in one piece,
+/// using the active emoji profile.
+#[tauri::command]
+pub fn export_discord_text(
+    path: PathBuf,
+    documents: State<'_, DocumentState>,
+    profiles: State<'_, EmojiProfileState>,
+) -> Result<()> {
+    let profiles = profiles.get();
+    let text = {
+        let current = documents.lock();
+        let document = &current.project.document;
+        match profiles.active_profile() {
+            Some(profile) => EmojiText::with_profile(document, profile),
+            None => EmojiText::from_document(document),
+        }
+    };
+    fs::write(&path, text.to_string())?;
+    Ok(())
+}
+
+/// The open document as Slack, Matrix or Unicode text, split into messages.
+/// Slack and Matrix take emoji names from the active emoji profile.
+#[tauri::command]
+pub fn text_messages(
+    target: TextTarget,
+    documents: State<'_, DocumentState>,
+    blocks: State<'_, BlockState>,
+    profiles: State<'_, EmojiProfileState>,
+) -> Result<Vec<String>> {
+    let profiles = profiles.get();
+    let profile = profiles.active_profile();
+    let current = documents.lock();
+    let document = &current.project.document;
+
+    let messages = match &target {
+        TextTarget::Slack(options) => Slack {
+            profile,
+            options: *options,
+        }
+        .messages(document)?,
+        TextTarget::Matrix(options) => Matrix { profile, options }.messages(document)?,
+        TextTarget::Unicode(options) => {
+            Unicode::new(blocks.lock().blocks(&document.pack.id)?, *options).messages(document)?
+        }
+    };
+    Ok(messages)
+}
+
+#[tauri::command]
+pub fn emoji_profiles(profiles: State<'_, EmojiProfileState>) -> EmojiProfiles {
+    profiles.get()
+}
+
+/// Adds or replaces the profile called `name`.
+#[tauri::command]
+pub fn save_emoji_profile(
+    name: String,
+    profile: EmojiProfile,
+    profiles: State<'_, EmojiProfileState>,
+) -> Result<EmojiProfiles> {
+    Ok(profiles.update(|profiles| profiles.insert(&name, profile))?)
+}
+
+#[tauri::command]
+pub fn delete_emoji_profile(
+    name: String,
+    profiles: State<'_, EmojiProfileState>,
+) -> Result<EmojiProfiles> {
+    Ok(profiles.update(|profiles| {
+        profiles.remove(&name);
+        Ok(())
+    })?)
+}
+
+/// Picks the profile applied to Discord exports, or none for `:blockId:`.
+#[tauri::command]
+pub fn set_active_emoji_profile(
+    name: Option<String>,
+    profiles: State<'_, EmojiProfileState>,
+) -> Result<EmojiProfiles> {
+    Ok(profiles.update(|profiles| profiles.set_active(name))?)
+}
@@ -0,0 +1,445 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::packs::BUILTIN_PACK_ID;
+
+/// Block painted into cells that have never been set: the built-in pack's
+/// `defaultBlock`, mirrored by `DEFAULT_BLOCK_ID` in src/blocks/index.ts.
+pub const DEFAULT_BLOCK_ID: &str = "12";
+
+/// Largest grid the editor allows along either axis.
+pub const MAX_GRID_SIZE: u32 = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GridError {
+    #[error("invalid grid size {cols}x{rows} (must be between 1 and {MAX_GRID_SIZE})")]
+    InvalidSize { cols: u32, rows: u32 },
+    #[error("cell ({x}, {y}) is outside the {cols}x{rows} grid")]
+    OutOfBounds {
+        x: u32,
+        y: u32,
+        cols: u32,
+        rows: u32,
+    },
+    #[error("invalid cell key {0:?}")]
+    InvalidCellKey(String),
+}
+
+/// Interned handle to a block id. Only meaningful for the grid that issued it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockKey(u32);
+
+/// Maps block id strings to compact keys so cells don't each own a `String`.
+#[derive(Debug, Clone, Default)]
+struct Interner {
+    ids: Vec<String>,
+    keys: HashMap<String, BlockKey>,
+}
+
+impl Interner {
+    fn intern(&mut self, id: &str) -> BlockKey {
+        if let Some(key) = self.keys.get(id) {
+            return *key;
+        }
+        let key = BlockKey(self.ids.len() as u32);
+        self.ids.push(id.to_owned());
+        self.keys.insert(id.to_owned(), key);
+        key
+    }
+
+    fn resolve(&self, key: BlockKey) -> &str {
+        &self.ids[key.0 as usize]
+    }
+}
+
+/// How far existing cells moved up/left during a resize.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResizeOffset {
+    pub x: u32,
+    pub y: u32,
+}
+
+/// A cols x rows grid of blocks stored densely in row-major order.
+/// Empty cells (`None`) render as the document's default block.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    cols: u32,
+    rows: u32,
+    cells: Vec<Option<BlockKey>>,
+    interner: Interner,
+}
+
+impl Grid {
+    pub fn new(cols: u32, rows: u32) -> Result<Self, GridError> {
+        check_size(cols, rows)?;
+        Ok(Self {
+            cols,
+            rows,
+            cells: vec![None; (cols * rows) as usize],
+            interner: Interner::default(),
+        })
+    }
+
+    pub fn cols(&self) -> u32 {
+        self.cols
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x < self.cols && y < self.rows
+    }
+
+    /// Returns the block id at `(x, y)`, or `None` if the cell is empty.
+    pub fn get(&self, x: u32, y: u32) -> Result<Option<&str>, GridError> {
+        let index = self.index(x, y)?;
+        Ok(self.cells[index].map(|key| self.interner.resolve(key)))
+    }
+
+    /// Returns the block id at `(x, y)`, falling back to `default_block`
+    /// for empty cells.
+    pub fn get_or<'a>(
+        &'a self,
+        x: u32,
+        y: u32,
+        default_block: &'a str,
+    ) -> Result<&'a str, GridError> {
+        Ok(self.get(x, y)?.unwrap_or(default_block))
+    }
+
+    /// Sets the cell at `(x, y)`, returning the block id it previously held.
+    pub fn set(
+        &mut self,
+        x: u32,
+        y: u32,
+        block_id: Option<&str>,
+    ) -> Result<Option<String>, GridError> {
+        let index = self.index(x, y)?;
+        let key = block_id.map(|id| self.interner.intern(id));
+        let previous = std::mem::replace(&mut self.cells[index], key);
+        Ok(previous.map(|key| self.interner.resolve(key).to_owned()))
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(None);
+    }
+
+    /// Iterates over every non-empty cell as `(x, y, block_id)`.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, u32, &str)> + '_ {
+        let cols = self.cols;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, cell)| {
+                cell.map(|key| {
+                    let index = index as u32;
+                    (index % cols, index / cols, self.interner.resolve(key))
+                })
+            })
+    }
+
+    /// Distinct block ids currently placed in the grid, sorted.
+    pub fn used_blocks(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.iter().map(|(_, _, id)| id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Resizes the grid, matching `CanvasController.resizeGrid`: when shrinking,
+    /// blank rows at the top and blank columns at the left are dropped first so
+    /// the artwork slides up/left instead of being cropped. A cell counts as
+    /// blank if it is empty or holds `default_block`.
+    pub fn resize(
+        &mut self,
+        cols: u32,
+        rows: u32,
+        default_block: &str,
+    ) -> Result<ResizeOffset, GridError> {
+        check_size(cols, rows)?;
+        if cols == self.cols && rows == self.rows {
+            return Ok(ResizeOffset::default());
+        }
+
+        let rows_to_remove = self.rows.saturating_sub(rows);
+        let cols_to_remove = self.cols.saturating_sub(cols);
+
+        let offset = ResizeOffset {
+            x: (0..self.cols)
+                .take(cols_to_remove as usize)
+                .take_while(|&x| (0..self.rows).all(|y| self.is_blank(x, y, default_block)))
+                .count() as u32,
+            y: (0..self.rows)
+                .take(rows_to_remove as usize)
+                .take_while(|&y| (0..self.cols).all(|x| self.is_blank(x, y, default_block)))
+                .count() as u32,
+        };
+
+        let mut cells = vec![None; (cols * rows) as usize];
+        for (index, cell) in self.cells.iter().enumerate() {
+            let Some(key) = cell else { continue };
+            let old_x = index as u32 % self.cols;
+            let old_y = index as u32 / self.cols;
+            let (Some(x), Some(y)) = (old_x.checked_sub(offset.x), old_y.checked_sub(offset.y))
+            else {
+                continue;
+            };
+            if x < cols && y < rows {
+                cells[(y * cols + x) as usize] = Some(*key);
+            }
+        }
+
+        self.cols = cols;
+        self.rows = rows;
+        self.cells = cells;
+        Ok(offset)
+    }
+
+    fn is_blank(&self, x: u32, y: u32, default_block: &str) -> bool {
+        match self.cells[(y * self.cols + x) as usize] {
+            None => true,
+            Some(key) => self.interner.resolve(key) == default_block,
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> Result<usize, GridError> {
+        if !self.contains(x, y) {
+            return Err(GridError::OutOfBounds {
+                x,
+                y,
+                cols: self.cols,
+                rows: self.rows,
+            });
+        }
+        Ok((y * self.cols + x) as usize)
+    }
+}
+
+impl PartialEq for Grid {
+    fn eq(&self, other: &Self) -> bool {
+        self.cols == other.cols && self.rows == other.rows && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Grid {}
+
+fn check_size(cols: u32, rows: u32) -> Result<(), GridError> {
+    if cols == 0 || rows == 0 || cols > MAX_GRID_SIZE || rows > MAX_GRID_SIZE {
+        return Err(GridError::InvalidSize { cols, rows });
+    }
+    Ok(())
+}
+
+/// A document in the shape the frontend `gridStore` uses: a sparse
+/// `"x,y" -> blockId` record plus the pack to draw it with. This is what
+/// crosses the Tauri boundary.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GridSnapshot {
+    pub cols: u32,
+    pub rows: u32,
+    pub cells: BTreeMap<String, String>,
+    pub pack: BlockPackRef,
+}
+
+impl From<&Document> for GridSnapshot {
+    fn from(document: &Document) -> Self {
+        let grid = &document.grid;
+        Self {
+            cols: grid.cols,
+            rows: grid.rows,
+            cells: grid
+                .iter()
+                .map(|(x, y, id)| (format!("{x},{y}"), id.to_owned()))
+                .collect(),
+            pack: document.pack.clone(),
+        }
+    }
+}
+
+impl TryFrom<&GridSnapshot> for Grid {
+    type Error = GridError;
+
+    fn try_from(snapshot: &GridSnapshot) -> Result<Self, Self::Error> {
+        let mut grid = Grid::new(snapshot.cols, snapshot.rows)?;
+        for (key, block_id) in &snapshot.cells {
+            let (x, y) = parse_cell_key(key)?;
+            grid.set(x, y, Some(block_id))?;
+        }
+        Ok(grid)
+    }
+}
+
+fn parse_cell_key(key: &str) -> Result<(u32, u32), GridError> {
+    key.split_once(',')
+        .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+        .ok_or_else(|| GridError::InvalidCellKey(key.to_owned()))
+}
+
+/// Where a document's blocks come from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BlockPackRef {
+    pub id: String,
+    pub default_block: String,
+}
+
+impl Default for BlockPackRef {
+    fn default() -> Self {
+        Self {
+            id: BUILTIN_PACK_ID.to_owned(),
+            default_block: DEFAULT_BLOCK_ID.to_owned(),
+        }
+    }
+}
+
+/// A grid together with the block pack it was drawn with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub grid: Grid,
+    pub pack: BlockPackRef,
+}
+
+impl Document {
+    pub fn new(cols: u32, rows: u32) -> Result<Self, GridError> {
+        Ok(Self {
+            grid: Grid::new(cols, rows)?,
+            pack: BlockPackRef::default(),
+        })
+    }
+
+    /// Block id shown at `(x, y)`, resolving empty cells to the pack default.
+    pub fn block_at(&self, x: u32, y: u32) -> Result<&str, GridError> {
+        self.grid.get_or(x, y, &self.pack.default_block)
+    }
+
+    pub fn resize(&mut self, cols: u32, rows: u32) -> Result<ResizeOffset, GridError> {
+        self.grid.resize(cols, rows, &self.pack.default_block)
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new(8, 8).expect("default grid size is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_from(rows: &[&str]) -> Grid {
+        let mut grid = Grid::new(rows[0].len() as u32, rows.len() as u32).unwrap();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c != '.' {
+                    grid.set(x as u32, y as u32, Some(&c.to_string())).unwrap();
+                }
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn get_and_set_are_bounds_checked() {
+        let mut grid = Grid::new(3, 2).unwrap();
+        assert_eq!(grid.set(2, 1, Some("05")), Ok(None));
+        assert_eq!(grid.get(2, 1), Ok(Some("05")));
+        assert_eq!(grid.set(2, 1, Some("06")), Ok(Some("05".to_owned())));
+
+        let out = GridError::OutOfBounds {
+            x: 3,
+            y: 0,
+            cols: 3,
+            rows: 2,
+        };
+        assert_eq!(grid.get(3, 0), Err(out.clone()));
+        assert_eq!(grid.set(3, 0, Some("01")), Err(out));
+        assert!(grid.get(0, 2).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        assert!(Grid::new(0, 4).is_err());
+        assert!(Grid::new(4, MAX_GRID_SIZE + 1).is_err());
+        let mut grid = Grid::new(4, 4).unwrap();
+        assert!(grid.resize(4, 0, DEFAULT_BLOCK_ID).is_err());
+        assert_eq!(grid.cols(), 4);
+    }
+
+    #[test]
+    fn interns_repeated_blocks() {
+        let mut grid = Grid::new(4, 4).unwrap();
+        for x in 0..4 {
+            grid.set(x, 0, Some("col_blue_hi")).unwrap();
+        }
+        assert_eq!(grid.interner.ids.len(), 1);
+        assert_eq!(grid.used_blocks(), vec!["col_blue_hi"]);
+    }
+
+    #[test]
+    fn growing_keeps_cells_in_place() {
+        let mut grid = grid_from(&["a.", ".b"]);
+        let offset = grid.resize(4, 3, DEFAULT_BLOCK_ID).unwrap();
+        assert_eq!(offset, ResizeOffset::default());
+        assert_eq!(grid, grid_from(&["a...", ".b..", "...."]));
+    }
+
+    #[test]
+    fn shrinking_slides_past_blank_rows_and_cols() {
+        let mut grid = grid_from(&["....", "..ab", "..cd"]);
+        let offset = grid.resize(2, 2, DEFAULT_BLOCK_ID).unwrap();
+        assert_eq!(offset, ResizeOffset { x: 2, y: 1 });
+        assert_eq!(grid, grid_from(&["ab", "cd"]));
+    }
+
+    #[test]
+    fn shrinking_slides_no_further_than_needed() {
+        let mut grid = grid_from(&["....", "....", "...a"]);
+        let offset = grid.resize(3, 2, DEFAULT_BLOCK_ID).unwrap();
+        assert_eq!(offset, ResizeOffset { x: 1, y: 1 });
+        assert_eq!(grid, grid_from(&["...", "..a"]));
+    }
+
+    #[test]
+    fn shrinking_crops_when_edges_are_not_blank() {
+        let mut grid = grid_from(&["ab.", "cd.", "..e"]);
+        let offset = grid.resize(2, 2, DEFAULT_BLOCK_ID).unwrap();
+        assert_eq!(offset, ResizeOffset::default());
+        assert_eq!(grid, grid_from(&["ab", "cd"]));
+    }
+
+    #[test]
+    fn default_block_counts_as_blank_when_resizing() {
+        let mut grid = grid_from(&["zz", "za"]);
+        let offset = grid.resize(1, 1, "z").unwrap();
+        assert_eq!(offset, ResizeOffset { x: 1, y: 1 });
+        assert_eq!(grid.get(0, 0), Ok(Some("a")));
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let grid = grid_from(&["a.", ".b"]);
+        let snapshot = GridSnapshot::from(&Document {
+            grid: grid.clone(),
+            pack: BlockPackRef::default(),
+        });
+        assert_eq!(snapshot.pack.id, BUILTIN_PACK_ID);
+        assert_eq!(snapshot.cells.get("1,1").map(String::as_str), Some("b"));
+        assert_eq!(Grid::try_from(&snapshot).unwrap(), grid);
+
+        let mut bad = snapshot.clone();
+        bad.cells.insert("2,0".to_owned(), "c".to_owned());
+        assert!(Grid::try_from(&bad).is_err());
+        bad.cells.clear();
+        bad.cells.insert("nope".to_owned(), "c".to_owned());
+        assert_eq!(
+            Grid::try_from(&bad),
+            Err(GridError::InvalidCellKey("nope".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::VecDeque;
+use std::mem;
+
+use serde::Serialize;
+
+use crate::grid::{Document, Grid, GridError, ResizeOffset};
+
+/// Default memory budget for undo history. A full clear of a 64x64 grid costs
+/// a few hundred KiB, so this keeps dozens of those or thousands of strokes.
+pub const DEFAULT_HISTORY_BUDGET: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellChange {
+    pub x: u32,
+    pub y: u32,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    Cells(Vec<CellChange>),
+    /// Redoing re-runs the resize, which is deterministic for a given grid
+    /// and default block, so the one in use at the time is kept in case the
+    /// document has switched packs since. Undoing slides the surviving cells
+    /// back by `offset` and restores the ones that were cropped away.
+    Resize {
+        from: (u32, u32),
+        to: (u32, u32),
+        default_block: String,
+        offset: ResizeOffset,
+        cropped: Vec<(u32, u32, String)>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    label: String,
+    change: Change,
+    /// Approximate heap footprint, used to enforce the budget.
+    size: usize,
+}
+
+impl Entry {
+    fn new(label: String, change: Change) -> Self {
+        let ids: usize = match &change {
+            Change::Cells(cells) => cells
+                .iter()
+                .map(|cell| {
+                    mem::size_of::<CellChange>()
+                        + cell.before.as_ref().map_or(0, String::capacity)
+                        + cell.after.as_ref().map_or(0, String::capacity)
+                })
+                .sum(),
+            Change::Resize {
+                default_block,
+                cropped,
+                ..
+            } => {
+                default_block.capacity()
+                    + cropped
+                        .iter()
+                        .map(|(_, _, id)| mem::size_of::<(u32, u32, String)>() + id.capacity())
+                        .sum::<usize>()
+            }
+        };
+        Self {
+            size: mem::size_of::<Self>() + label.capacity() + ids,
+            label,
+            change,
+        }
+    }
+}
+
+/// What the frontend and menu need to know about the history.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryStatus {
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub undo_label: Option<String>,
+    pub redo_label: Option<String>,
+}
+
+/// Undo/redo stacks of per-operation diffs. Every change to a document's grid
+/// should go through [`History::edit`] or [`History::resize`] so the stacks
+/// stay consistent with the grid they apply to.
+#[derive(Debug, Clone)]
+pub struct History {
+    undo: VecDeque<Entry>,
+    redo: Vec<Entry>,
+    bytes: usize,
+    budget: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::with_budget(DEFAULT_HISTORY_BUDGET)
+    }
+}
+
+impl History {
+    pub fn with_budget(budget: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            bytes: 0,
+            budget,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn status(&self) -> HistoryStatus {
+        HistoryStatus {
+            can_undo: self.can_undo(),
+            can_redo: self.can_redo(),
+            undo_label: self.undo.back().map(|entry| entry.label.clone()),
+            redo_label: self.redo.last().map(|entry| entry.label.clone()),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.bytes = 0;
+    }
+
+    /// Sets each `(x, y, block_id)` cell and records the operation as one undo
+    /// step. Nothing is applied if any cell is out of bounds. Returns whether
+    /// the grid actually changed.
+    pub fn edit(
+        &mut self,
+        document: &mut Document,
+        label: impl Into<String>,
+        cells: impl IntoIterator<Item = (u32, u32, Option<String>)>,
+    ) -> Result<bool, GridError> {
+        let grid = &mut document.grid;
+        let cells: Vec<_> = cells.into_iter().collect();
+        for (x, y, _) in &cells {
+            grid.get(*x, *y)?;
+        }
+
+        let mut changes = Vec::new();
+        for (x, y, after) in cells {
+            let before = grid.set(x, y, after.as_deref())?;
+            if before != after {
+                changes.push(CellChange {
+                    x,
+                    y,
+                    before,
+                    after,
+                });
+            }
+        }
+
+        if changes.is_empty() {
+            return Ok(false);
+        }
+        self.push(Entry::new(label.into(), Change::Cells(changes)));
+        Ok(true)
+    }
+
+    /// Resizes the document (see [`Grid::resize`]) as one undo step.
+    pub fn resize(
+        &mut self,
+        document: &mut Document,
+        cols: u32,
+        rows: u32,
+        label: impl Into<String>,
+    ) -> Result<ResizeOffset, GridError> {
+        let from = (document.grid.cols(), document.grid.rows());
+        if from == (cols, rows) {
+            return Ok(ResizeOffset::default());
+        }
+
+        let before = document.grid.clone();
+        let offset = document.resize(cols, rows)?;
+        let cropped = before
+            .iter()
+            .filter(|&(x, y, _)| {
+                x < offset.x || y < offset.y || x - offset.x >= cols || y - offset.y >= rows
+            })
+            .map(|(x, y, id)| (x, y, id.to_owned()))
+            .collect();
+
+        self.push(Entry::new(
+            label.into(),
+            Change::Resize {
+                from,
+                to: (cols, rows),
+                default_block: document.pack.default_block.clone(),
+                offset,
+                cropped,
+            },
+        ));
+        Ok(offset)
+    }
+
+    /// Reverts the most recent step. Returns its label, or `None` if there
+    /// was nothing to undo.
+    pub fn undo(&mut self, document: &mut Document) -> Result<Option<String>, GridError> {
+        let Some(entry) = self.undo.pop_back() else {
+            return Ok(None);
+        };
+
+        match &entry.change {
+            Change::Cells(changes) => {
+                for change in changes.iter().rev() {
+                    document
+                        .grid
+                        .set(change.x, change.y, change.before.as_deref())?;
+                }
+            }
+            Change::Resize {
+                from,
+                offset,
+                cropped,
+                ..
+            } => {
+                let mut grid = Grid::new(from.0, from.1)?;
+                for (x, y, id) in document.grid.iter() {
+                    grid.set(x + offset.x, y + offset.y, Some(id))?;
+                }
+                for (x, y, id) in cropped {
+                    grid.set(*x, *y, Some(id))?;
+                }
+                document.grid = grid;
+            }
+        }
+
+        let label = entry.label.clone();
+        self.bytes -= entry.size;
+        self.redo.push(entry);
+        Ok(Some(label))
+    }
+
+    /// Re-applies the most recently undone step. Returns its label, or `None`
+    /// if there was nothing to redo.
+    pub fn redo(&mut self, document: &mut Document) -> Result<Option<String>, GridError> {
+        let Some(entry) = self.redo.pop() else {
+            return Ok(None);
+        };
+
+        match &entry.change {
+            Change::Cells(changes) => {
+                for change in changes {
+                    document
+                        .grid
+                        .set(change.x, change.y, change.after.as_deref())?;
+                }
+            }
+            Change::Resize {
+                to, default_block, ..
+            } => {
+                document.grid.resize(to.0, to.1, default_block)?;
+            }
+        }
+
+        let label = entry.label.clone();
+        self.bytes += entry.size;
+        self.undo.push_back(entry);
+        self.enforce_budget();
+        Ok(Some(label))
+    }
+
+    fn push(&mut self, entry: Entry) {
+        self.redo.clear();
+        self.bytes += entry.size;
+        self.undo.push_back(entry);
+        self.enforce_budget();
+    }
+
+    /// Drops the oldest steps until the history fits the budget, always
+    /// keeping the most recent one.
+    fn enforce_budget(&mut self) {
+        while self.bytes > self.budget && self.undo.len() > 1 {
+            if let Some(entry) = self.undo.pop_front() {
+                self.bytes -= entry.size;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::GridSnapshot;
+
+    fn paint(
+        history: &mut History,
+        document: &mut Document,
+        label: &str,
+        cells: &[(u32, u32, &str)],
+    ) {
+        let cells = cells.iter().map(|&(x, y, id)| (x, y, Some(id.to_owned())));
+        assert!(history.edit(document, label, cells).unwrap());
+    }
+
+    #[test]
+    fn undo_and_redo_cell_edits() {
+        let mut document = Document::new(4, 4).unwrap();
+        let mut history = History::default();
+        paint(
+            &mut history,
+            &mut document,
+            "Pencil",
+            &[(0, 0, "01"), (1, 0, "01")],
+        );
+        let painted = GridSnapshot::from(&document);
+        paint(&mut history, &mut document, "Flood Fill", &[(0, 0, "02")]);
+
+        assert_eq!(history.status().undo_label.as_deref(), Some("Flood Fill"));
+        assert_eq!(
+            history.undo(&mut document).unwrap().as_deref(),
+            Some("Flood Fill")
+        );
+        assert_eq!(GridSnapshot::from(&document), painted);
+        assert_eq!(history.status().redo_label.as_deref(), Some("Flood Fill"));
+
+        assert_eq!(
+            history.undo(&mut document).unwrap().as_deref(),
+            Some("Pencil")
+        );
+        assert_eq!(document.grid.iter().count(), 0);
+        assert_eq!(history.undo(&mut document).unwrap(), None);
+
+        history.redo(&mut document).unwrap();
+        history.redo(&mut document).unwrap();
+        assert_eq!(document.grid.get(0, 0).unwrap(), Some("02"));
+        assert_eq!(document.grid.get(1, 0).unwrap(), Some("01"));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn repainting_a_cell_within_one_edit_restores_the_original() {
+        let mut document = Document::new(2, 2).unwrap();
+        let mut history = History::default();
+        paint(
+            &mut history,
+            &mut document,
+            "Pencil",
+            &[(0, 0, "01"), (0, 0, "02")],
+        );
+        history.undo(&mut document).unwrap();
+        assert_eq!(document.grid.get(0, 0).unwrap(), None);
+    }
+
+    #[test]
+    fn new_edits_clear_redo() {
+        let mut document = Document::new(2, 2).unwrap();
+        let mut history = History::default();
+        paint(&mut history, &mut document, "Pencil", &[(0, 0, "01")]);
+        history.undo(&mut document).unwrap();
+        paint(&mut history, &mut document, "Pencil", &[(1, 1, "01")]);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn no_op_edits_are_not_recorded() {
+        let mut document = Document::new(2, 2).unwrap();
+        let mut history = History::default();
+        paint(&mut history, &mut document, "Pencil", &[(0, 0, "01")]);
+        let unchanged = [(0, 0, Some("01".to_owned()))];
+        assert!(!history.edit(&mut document, "Pencil", unchanged).unwrap());
+        assert_eq!(history.undo.len(), 1);
+    }
+
+    #[test]
+    fn out_of_bounds_edits_apply_nothing() {
+        let mut document = Document::new(2, 2).unwrap();
+        let mut history = History::default();
+        let cells = [(0, 0, Some("01".to_owned())), (2, 0, Some("01".to_owned()))];
+        assert!(history.edit(&mut document, "Pencil", cells).is_err());
+        assert_eq!(document.grid.get(0, 0).unwrap(), None);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn undoing_a_resize_restores_cropped_and_slid_cells() {
+        let mut document = Document::new(4, 3).unwrap();
+        let mut history = History::default();
+        paint(
+            &mut history,
+            &mut document,
+            "Pencil",
+            &[(2, 1, "a"), (3, 2, "b"), (0, 2, "c")],
+        );
+        let before = GridSnapshot::from(&document);
+
+        history.resize(&mut document, 2, 2, "Resize Grid").unwrap();
+        let after = GridSnapshot::from(&document);
+        assert_eq!((document.grid.cols(), document.grid.rows()), (2, 2));
+
+        history.undo(&mut document).unwrap();
+        assert_eq!(GridSnapshot::from(&document), before);
+
+        history.redo(&mut document).unwrap();
+        assert_eq!(GridSnapshot::from(&document), after);
+    }
+
+    #[test]
+    fn redoing_a_resize_ignores_a_later_pack_switch() {
+        let mut document = Document::new(3, 2).unwrap();
+        document.pack.default_block = "03".into();
+        let mut history = History::default();
+        paint(
+            &mut history,
+            &mut document,
+            "Pencil",
+            &[(0, 0, "03"), (0, 1, "03"), (2, 0, "a")],
+        );
+        let before = GridSnapshot::from(&document).cells;
+
+        // Column 0 is all default blocks, so it's the one cropped away.
+        history.resize(&mut document, 2, 2, "Resize Grid").unwrap();
+        let after = GridSnapshot::from(&document).cells;
+        assert_eq!(document.grid.get(1, 0).unwrap(), Some("a"));
+
+        document.pack.default_block = "12".into();
+        history.undo(&mut document).unwrap();
+        assert_eq!(GridSnapshot::from(&document).cells, before);
+        history.redo(&mut document).unwrap();
+        assert_eq!(GridSnapshot::from(&document).cells, after);
+        history.undo(&mut document).unwrap();
+        assert_eq!(GridSnapshot::from(&document).cells, before);
+    }
+
+    #[test]
+    fn budget_drops_oldest_entries() {
+        let mut document = Document::new(8, 8).unwrap();
+        let mut history = History::with_budget(2_000);
+        for i in 0..100u32 {
+            paint(
+                &mut history,
+                &mut document,
+                "Pencil",
+                &[(i % 8, i / 8 % 8, "01"), (0, 7, &i.to_string())],
+            );
+        }
+        assert!(history.bytes <= 2_000);
+        assert!(history.undo.len() > 1 && history.undo.len() < 100);
+        assert_eq!(
+            history.bytes,
+            history.undo.iter().map(|e| e.size).sum::<usize>()
+        );
+    }
+}
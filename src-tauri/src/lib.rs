@@ -1,7 +1,26 @@
-use tauri::{
-    menu::{MenuBuilder, MenuItemBuilder, PredefinedMenuItem, SubmenuBuilder},
-    Emitter, Manager,
-};
+mod autosave;
+pub mod blocks;
+mod commands;
+pub mod convert;
+pub mod discord;
+pub mod emoji;
+mod error;
+mod export;
+pub mod grid;
+pub mod history;
+mod menu;
+pub mod packs;
+pub mod project;
+mod recent;
+pub mod render;
+mod state;
+pub mod text;
+mod watcher;
+
+use tauri::{Manager, RunEvent};
+
+use autosave::Autosave;
+use state::{BlockState, DocumentState, EmojiProfileState, RecentState};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -9,49 +28,74 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(DocumentState::default())
+        .invoke_handler(tauri::generate_handler![
+            commands::open_document,
+            commands::set_document_pack,
+            commands::save_document,
+            commands::document_path,
+            commands::apply_edit,
+            commands::resize_document,
+            commands::undo,
+            commands::redo,
+            commands::resync_document,
+            commands::can_undo,
+            commands::can_redo,
+            commands::new_document,
+            commands::open_recent,
+            commands::import_discord_text,
+            commands::import_image,
+            commands::block_catalog,
+            commands::search_blocks,
+            commands::import_block,
+            commands::export_block_pack,
+            commands::import_block_pack,
+            export::render_png,
+            export::export_animation,
+            export::export_svg,
+            export::discord_messages,
+            export::export_discord_text,
+            export::text_messages,
+            export::emoji_profiles,
+            export::save_emoji_profile,
+            export::delete_emoji_profile,
+            export::set_active_emoji_profile,
+            autosave::check_recovery,
+            autosave::restore_recovery,
+            autosave::discard_recovery,
+        ])
         .setup(|app| {
-            // Build the Edit menu
-            let undo = MenuItemBuilder::with_id("edit:undo", "Undo")
-                .accelerator("CmdOrCtrl+Z")
-                .build(app)?;
-            let redo = MenuItemBuilder::with_id("edit:redo", "Redo")
-                .accelerator("CmdOrCtrl+Shift+Z")
-                .build(app)?;
-            let clear = MenuItemBuilder::with_id("edit:clear", "Clear Grid").build(app)?;
-
-            let edit_menu = SubmenuBuilder::new(app, "Edit")
-                .item(&undo)
-                .item(&redo)
-                .separator()
-                .item(&clear)
-                .build()?;
+            autosave::init(app.handle())?;
 
-            // Build the View menu
-            let reset_view = MenuItemBuilder::with_id("view:reset", "Reset View")
-                .accelerator("CmdOrCtrl+0")
-                .build(app)?;
+            let config_dir = app.path().app_config_dir()?;
+            app.manage(RecentState::load(config_dir.join("recent.json")));
+            app.manage(EmojiProfileState::load(
+                config_dir.join("emoji-profiles.json"),
+            ));
 
-            let view_menu = SubmenuBuilder::new(app, "View").item(&reset_view).build()?;
-
-            // Build the full menu bar
-            let menu = MenuBuilder::new(app)
-                .item(&PredefinedMenuItem::about(app, Some("binblock++"), None)?)
-                .item(&edit_menu)
-                .item(&view_menu)
-                .build()?;
+            let user_packs_dir = app.path().app_data_dir()?.join("packs");
+            if let Err(err) = std::fs::create_dir_all(&user_packs_dir) {
+                eprintln!("failed to create {}: {err}", user_packs_dir.display());
+            }
+            app.manage(BlockState::load(
+                app.path().resource_dir()?.join("packs"),
+                user_packs_dir,
+            ));
+            if let Err(err) = watcher::init(app.handle()) {
+                eprintln!("failed to watch block packs: {err}");
+            }
 
+            let menu = menu::build(app.handle())?;
             app.set_menu(menu)?;
-
-            // Handle menu events
-            app.on_menu_event(move |app_handle, event| {
-                let id = event.id().as_ref();
-                if let Some(window) = app_handle.get_webview_window("main") {
-                    let _ = window.emit("menu-event", id);
-                }
-            });
+            app.on_menu_event(menu::handle_event);
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let RunEvent::Exit = event {
+                app_handle.state::<Autosave>().end_session();
+            }
+        });
 }
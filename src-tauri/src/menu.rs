@@ -0,0 +1,232 @@
+mod action;
+
+use std::path::PathBuf;
+
+use tauri::{
+    menu::{
+        Menu, MenuBuilder, MenuEvent, MenuItem, MenuItemBuilder, PredefinedMenuItem, Submenu,
+        SubmenuBuilder,
+    },
+    AppHandle, Emitter, Manager, Runtime,
+};
+
+use crate::commands;
+use crate::history::HistoryStatus;
+use crate::state::RecentState;
+
+pub use action::MenuAction;
+
+#[cfg(target_os = "macos")]
+const QUIT_ACCELERATOR: &str = "Cmd+Q";
+#[cfg(target_os = "windows")]
+const QUIT_ACCELERATOR: &str = "Alt+F4";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const QUIT_ACCELERATOR: &str = "Ctrl+Q";
+
+/// Menu items that change after the menu bar has been built.
+pub struct MenuHandles<R: Runtime> {
+    open_recent: Submenu<R>,
+    undo: MenuItem<R>,
+    redo: MenuItem<R>,
+}
+
+pub fn build<R: Runtime>(app: &AppHandle<R>) -> tauri::Result<Menu<R>> {
+    // Build the File menu
+    let new = action_item(MenuAction::New, "New")
+        .accelerator("CmdOrCtrl+N")
+        .build(app)?;
+    let open = action_item(MenuAction::Open, "Open…")
+        .accelerator("CmdOrCtrl+O")
+        .build(app)?;
+    let open_recent = SubmenuBuilder::with_id(app, "file:open-recent", "Open Recent").build()?;
+    fill_recent(app, &open_recent, &app.state::<RecentState>().paths())?;
+    let import_image = action_item(MenuAction::ImportImage, "Import Image…").build(app)?;
+    let save = action_item(MenuAction::Save, "Save")
+        .accelerator("CmdOrCtrl+S")
+        .build(app)?;
+    let save_as = action_item(MenuAction::SaveAs, "Save As…")
+        .accelerator("CmdOrCtrl+Shift+S")
+        .build(app)?;
+    let export_png = action_item(MenuAction::ExportPng, "Export PNG…")
+        .accelerator("CmdOrCtrl+E")
+        .build(app)?;
+    let export_animation =
+        action_item(MenuAction::ExportAnimation, "Export Animation…").build(app)?;
+    let export_svg = action_item(MenuAction::ExportSvg, "Export SVG…").build(app)?;
+    let export_discord = action_item(MenuAction::ExportDiscord, "Export Discord Text…")
+        .accelerator("CmdOrCtrl+Shift+E")
+        .build(app)?;
+    let close_window = action_item(MenuAction::CloseWindow, "Close Window")
+        .accelerator("CmdOrCtrl+W")
+        .build(app)?;
+    let quit = action_item(MenuAction::Quit, "Quit")
+        .accelerator(QUIT_ACCELERATOR)
+        .build(app)?;
+
+    let file_menu = SubmenuBuilder::new(app, "File")
+        .item(&new)
+        .item(&open)
+        .item(&open_recent)
+        .item(&import_image)
+        .separator()
+        .item(&save)
+        .item(&save_as)
+        .separator()
+        .item(&export_png)
+        .item(&export_animation)
+        .item(&export_svg)
+        .item(&export_discord)
+        .separator()
+        .item(&close_window)
+        .item(&quit)
+        .build()?;
+
+    // Build the Edit menu
+    let undo = action_item(MenuAction::Undo, "Undo")
+        .accelerator("CmdOrCtrl+Z")
+        .enabled(false)
+        .build(app)?;
+    let redo = action_item(MenuAction::Redo, "Redo")
+        .accelerator("CmdOrCtrl+Shift+Z")
+        .enabled(false)
+        .build(app)?;
+    let clear = action_item(MenuAction::Clear, "Clear Grid").build(app)?;
+
+    let edit_menu = SubmenuBuilder::new(app, "Edit")
+        .item(&undo)
+        .item(&redo)
+        .separator()
+        .item(&clear)
+        .build()?;
+
+    // Build the View menu
+    let reset_view = action_item(MenuAction::ResetView, "Reset View")
+        .accelerator("CmdOrCtrl+0")
+        .build(app)?;
+
+    let view_menu = SubmenuBuilder::new(app, "View").item(&reset_view).build()?;
+
+    // Build the full menu bar
+    let menu = MenuBuilder::new(app)
+        .item(&PredefinedMenuItem::about(app, Some("binblock++"), None)?)
+        .item(&file_menu)
+        .item(&edit_menu)
+        .item(&view_menu)
+        .build()?;
+
+    app.manage(MenuHandles {
+        open_recent,
+        undo,
+        redo,
+    });
+    Ok(menu)
+}
+
+/// Enables Undo/Redo only when there is something to undo or redo, and names
+/// the operation in their labels, e.g. "Undo Flood Fill".
+pub fn set_history_status<R: Runtime>(
+    app: &AppHandle<R>,
+    status: &HistoryStatus,
+) -> tauri::Result<()> {
+    let handles = app.state::<MenuHandles<R>>();
+    handles.undo.set_enabled(status.can_undo)?;
+    handles
+        .undo
+        .set_text(history_label("Undo", status.undo_label.as_deref()))?;
+    handles.redo.set_enabled(status.can_redo)?;
+    handles
+        .redo
+        .set_text(history_label("Redo", status.redo_label.as_deref()))
+}
+
+fn history_label(verb: &str, operation: Option<&str>) -> String {
+    match operation {
+        Some(operation) => format!("{verb} {operation}"),
+        None => verb.to_owned(),
+    }
+}
+
+/// Rebuilds the Open Recent submenu from `paths`.
+pub fn set_recent_files<R: Runtime>(app: &AppHandle<R>, paths: &[PathBuf]) -> tauri::Result<()> {
+    let submenu = &app.state::<MenuHandles<R>>().open_recent;
+    for item in submenu.items()? {
+        submenu.remove(&item)?;
+    }
+    fill_recent(app, submenu, paths)
+}
+
+fn fill_recent<R: Runtime>(
+    app: &AppHandle<R>,
+    submenu: &Submenu<R>,
+    paths: &[PathBuf],
+) -> tauri::Result<()> {
+    if paths.is_empty() {
+        let none = MenuItemBuilder::with_id("file:no-recent", "No Recent Files")
+            .enabled(false)
+            .build(app)?;
+        submenu.append(&none)?;
+    }
+
+    for (index, path) in paths.iter().enumerate() {
+        let label = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy();
+        let item = action_item(MenuAction::OpenRecent { index }, label).build(app)?;
+        submenu.append(&item)?;
+    }
+
+    let clear = action_item(MenuAction::ClearRecent, "Clear Recent")
+        .enabled(!paths.is_empty())
+        .build(app)?;
+    submenu.append(&PredefinedMenuItem::separator(app)?)?;
+    submenu.append(&clear)
+}
+
+fn action_item<S: AsRef<str>>(action: MenuAction, text: S) -> MenuItemBuilder {
+    MenuItemBuilder::with_id(action.id(), text)
+}
+
+/// Handles window-level actions natively and forwards everything else to the
+/// frontend over the `menu-event` channel as a serialized [`MenuAction`].
+pub fn handle_event<R: Runtime>(app: &AppHandle<R>, event: MenuEvent) {
+    let id = event.id().as_ref();
+    let Some(action) = MenuAction::from_id(id) else {
+        eprintln!("ignoring unknown menu item {id:?}");
+        return;
+    };
+
+    match action {
+        MenuAction::CloseWindow => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.close();
+            }
+        }
+        MenuAction::Quit => app.exit(0),
+        MenuAction::Undo | MenuAction::Redo => {
+            let result = if action == MenuAction::Undo {
+                commands::undo(app.clone())
+            } else {
+                commands::redo(app.clone())
+            };
+            if let Err(err) = result {
+                eprintln!("failed to {action:?}: {err}");
+            }
+        }
+        MenuAction::ClearRecent => {
+            let result = app
+                .state::<RecentState>()
+                .update(|files| files.clear())
+                .map_err(tauri::Error::from)
+                .and_then(|paths| set_recent_files(app, &paths));
+            if let Err(err) = result {
+                eprintln!("failed to clear recent files: {err}");
+            }
+        }
+        _ => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.emit("menu-event", action);
+            }
+        }
+    }
+}
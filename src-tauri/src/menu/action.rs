@@ -0,0 +1,169 @@
+//! Everything a menu item can ask the app to do.
+//!
+//! Menu item ids are derived from [`MenuAction::id`] and parsed back with
+//! [`MenuAction::from_id`], so the set of ids lives in one place. Actions the
+//! backend doesn't handle itself are emitted to the frontend on the
+//! `menu-event` channel using the serde representation below; the matching
+//! TypeScript type is generated into src/bindings by `cargo test`.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "type", rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum MenuAction {
+    New,
+    Open,
+    OpenRecent { index: usize },
+    ClearRecent,
+    ImportImage,
+    Save,
+    SaveAs,
+    ExportPng,
+    ExportAnimation,
+    ExportSvg,
+    ExportDiscord,
+    CloseWindow,
+    Quit,
+    Undo,
+    Redo,
+    Clear,
+    ResetView,
+}
+
+const OPEN_RECENT_PREFIX: &str = "file:open-recent:";
+
+impl MenuAction {
+    /// Every action that doesn't carry a payload, and so every one
+    /// `from_id` can return besides `OpenRecent`. Kept in step with the enum
+    /// by the `unit_lists_every_action_without_a_payload` test.
+    pub const UNIT: [MenuAction; 16] = [
+        MenuAction::New,
+        MenuAction::Open,
+        MenuAction::ClearRecent,
+        MenuAction::ImportImage,
+        MenuAction::Save,
+        MenuAction::SaveAs,
+        MenuAction::ExportPng,
+        MenuAction::ExportAnimation,
+        MenuAction::ExportSvg,
+        MenuAction::ExportDiscord,
+        MenuAction::CloseWindow,
+        MenuAction::Quit,
+        MenuAction::Undo,
+        MenuAction::Redo,
+        MenuAction::Clear,
+        MenuAction::ResetView,
+    ];
+
+    /// The id of the menu item that triggers this action.
+    pub fn id(&self) -> String {
+        let id = match self {
+            MenuAction::New => "file:new",
+            MenuAction::Open => "file:open",
+            MenuAction::OpenRecent { index } => return format!("{OPEN_RECENT_PREFIX}{index}"),
+            MenuAction::ClearRecent => "file:clear-recent",
+            MenuAction::ImportImage => "file:import-image",
+            MenuAction::Save => "file:save",
+            MenuAction::SaveAs => "file:save-as",
+            MenuAction::ExportPng => "file:export-png",
+            MenuAction::ExportAnimation => "file:export-animation",
+            MenuAction::ExportSvg => "file:export-svg",
+            MenuAction::ExportDiscord => "file:export-discord",
+            MenuAction::CloseWindow => "file:close-window",
+            MenuAction::Quit => "file:quit",
+            MenuAction::Undo => "edit:undo",
+            MenuAction::Redo => "edit:redo",
+            MenuAction::Clear => "edit:clear",
+            MenuAction::ResetView => "view:reset",
+        };
+        id.to_owned()
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        if let Some(index) = id.strip_prefix(OPEN_RECENT_PREFIX) {
+            return index
+                .parse()
+                .ok()
+                .map(|index| MenuAction::OpenRecent { index });
+        }
+        Self::UNIT.into_iter().find(|action| action.id() == id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_round_trip() {
+        for action in MenuAction::UNIT {
+            assert_eq!(MenuAction::from_id(&action.id()), Some(action));
+        }
+        let recent = MenuAction::OpenRecent { index: 3 };
+        assert_eq!(recent.id(), "file:open-recent:3");
+        assert_eq!(MenuAction::from_id("file:open-recent:3"), Some(recent));
+    }
+
+    #[test]
+    fn unit_lists_every_action_without_a_payload() {
+        // The match has no wildcard arm, so a new variant stops this
+        // compiling until it's listed here, and then fails the test until
+        // it's in `UNIT` too.
+        macro_rules! unit {
+            ($($variant:ident),* $(,)?) => {{
+                let _ = |action: MenuAction| match action {
+                    $(MenuAction::$variant)|* => {}
+                    MenuAction::OpenRecent { .. } => {}
+                };
+                [$(MenuAction::$variant),*]
+            }};
+        }
+        let listed = unit![
+            New,
+            Open,
+            ClearRecent,
+            ImportImage,
+            Save,
+            SaveAs,
+            ExportPng,
+            ExportAnimation,
+            ExportSvg,
+            ExportDiscord,
+            CloseWindow,
+            Quit,
+            Undo,
+            Redo,
+            Clear,
+            ResetView,
+        ];
+        let ids = |actions: &[MenuAction]| {
+            actions
+                .iter()
+                .map(MenuAction::id)
+                .collect::<std::collections::BTreeSet<_>>()
+        };
+        assert_eq!(ids(&MenuAction::UNIT), ids(&listed));
+        assert_eq!(MenuAction::UNIT.len(), listed.len());
+    }
+
+    #[test]
+    fn unknown_ids_are_rejected() {
+        assert_eq!(MenuAction::from_id("file:nope"), None);
+        assert_eq!(MenuAction::from_id("file:open-recent:x"), None);
+        assert_eq!(MenuAction::from_id("file:no-recent"), None);
+    }
+
+    #[test]
+    fn serializes_with_type_tag() {
+        assert_eq!(
+            serde_json::to_value(MenuAction::SaveAs).unwrap(),
+            serde_json::json!({ "type": "saveAs" })
+        );
+        assert_eq!(
+            serde_json::to_value(MenuAction::OpenRecent { index: 2 }).unwrap(),
+            serde_json::json!({ "type": "openRecent", "index": 2 })
+        );
+    }
+}
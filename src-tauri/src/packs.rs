@@ -0,0 +1,689 @@
+//! Block packs: folders of block images described by a `pack.json` manifest.
+//! Packs are found at runtime in the bundled resources and the user's packs
+//! directory, so adding blocks doesn't need a rebuild. Each pack is its own
+//! namespace of block ids, and a document draws from the pack its
+//! `BlockPackRef` names.
+//!
+//! A pack with a broken manifest is skipped. Within a usable pack, blocks
+//! whose images are missing or invalid are dropped; either way the problem is
+//! kept so the frontend can show it.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::blocks::{Block, BlockError, BlockSet};
+use crate::grid::BlockPackRef;
+use groups::SpecifierError;
+
+pub mod archive;
+pub mod groups;
+pub mod import;
+pub mod search;
+pub mod watch;
+
+pub const MANIFEST_FILE: &str = "pack.json";
+
+/// Id of the pack shipped with the app, used by new documents.
+pub const BUILTIN_PACK_ID: &str = "builtin";
+
+/// Largest block image accepted, in pixels along each side. Blocks needn't be
+/// square; they're stretched to fill their cell.
+pub const MAX_BLOCK_SIZE: u32 = 256;
+
+/// Image types a block may be, in the order they're looked for.
+const BLOCK_EXTENSIONS: [(&str, &str); 2] = [("png", "image/png"), ("gif", "image/gif")];
+
+#[derive(Debug, thiserror::Error)]
+pub enum PackError {
+    #[error("failed to read {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("block pack manifest {} is not valid: {source}", path.display())]
+    Manifest {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("{0:?} is not a valid pack id; use lowercase letters, digits, - and _")]
+    InvalidPackId(String),
+    #[error("block pack {pack:?} is already loaded; skipping the copy in {}", path.display())]
+    DuplicatePack { pack: String, path: PathBuf },
+    #[error("block pack {0:?} is not installed")]
+    UnknownPack(String),
+    #[error("block pack {pack:?}: {block:?} is not a valid block id")]
+    InvalidBlockId { pack: String, block: String },
+    #[error("block pack {pack:?} lists block {block:?} more than once")]
+    DuplicateBlock { pack: String, block: String },
+    #[error("block pack {pack:?}: no .png or .gif image for block {block:?}")]
+    MissingImage { pack: String, block: String },
+    #[error(transparent)]
+    Block(#[from] BlockError),
+    #[error("failed to read image {}: {source}", path.display())]
+    Image {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    #[error(
+        "block image {} is {width}x{height}; blocks must be 1 to {MAX_BLOCK_SIZE}px on each side",
+        path.display()
+    )]
+    BadDimensions {
+        path: PathBuf,
+        width: u32,
+        height: u32,
+    },
+    #[error("block pack {pack:?}: default block {block:?} is not one of its blocks")]
+    UnknownDefaultBlock { pack: String, block: String },
+    #[error("block pack {pack:?} has names and tags for unknown block {block:?}")]
+    UnknownBlockInfo { pack: String, block: String },
+    #[error("block pack {pack:?}: group {group:?} {source}")]
+    GroupSpecifier {
+        pack: String,
+        group: String,
+        source: SpecifierError,
+    },
+    #[error("block pack archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+    #[error("block pack archive entry {0:?} is not a plain file name")]
+    UnsafeEntry(String),
+    #[error("block pack archive has more than one {0:?}")]
+    DuplicateEntry(String),
+    #[error("block pack archive entry {0:?} is too large")]
+    EntryTooLarge(String),
+    #[error("block pack archive has too many entries ({0})")]
+    TooManyEntries(usize),
+    #[error("block pack archive is too large to unpack")]
+    ArchiveTooLarge,
+    #[error("block pack archive has no {MANIFEST_FILE}")]
+    MissingManifest,
+    #[error("block pack emoji map is not valid: {0}")]
+    EmojiMap(serde_json::Error),
+    #[error("a block pack with id {0:?} is already installed")]
+    PackExists(String),
+}
+
+/// A named run of blocks shown together in the palette. In a manifest its
+/// blocks are specifiers, expanded by `groups::expand`; everywhere else
+/// they're block ids.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BlockGroup {
+    pub name: String,
+    pub blocks: Vec<String>,
+}
+
+/// What people call a block besides its id, which is only a file name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BlockInfo {
+    /// Shown in place of the id.
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Other names the block can be searched by.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// The contents of `pack.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackManifest {
+    pub id: String,
+    pub name: String,
+    /// Block ids in palette order. Each is the stem of a `.png` or `.gif`
+    /// next to the manifest.
+    pub blocks: Vec<String>,
+    #[serde(default)]
+    pub groups: Vec<BlockGroup>,
+    /// Names, tags and aliases by block id. Blocks needn't have any.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub block_info: BTreeMap<String, BlockInfo>,
+    /// Shown in empty cells.
+    pub default_block: String,
+}
+
+impl PackManifest {
+    pub fn load(path: &Path) -> Result<Self, PackError> {
+        let bytes = fs::read(path).map_err(|source| PackError::Io {
+            path: path.to_owned(),
+            source,
+        })?;
+        serde_json::from_slice(&bytes).map_err(|source| PackError::Manifest {
+            path: path.to_owned(),
+            source,
+        })
+    }
+}
+
+/// Pack ids are 1 to 64 lowercase letters, digits, hyphens or underscores.
+pub fn is_valid_pack_id(id: &str) -> bool {
+    (1..=64).contains(&id.len())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+/// Block ids are file stems, so they can't name other directories.
+pub fn is_valid_block_id(id: &str) -> bool {
+    !id.is_empty() && !id.starts_with('.') && !id.contains(['/', '\\', '\0'])
+}
+
+/// A loaded pack. Its manifest only lists the blocks that loaded and the
+/// group specifiers that matched some of them.
+#[derive(Debug, Clone)]
+pub struct Pack {
+    pub manifest: PackManifest,
+    pub dir: PathBuf,
+    pub blocks: BlockSet,
+    /// The manifest's groups with their specifiers expanded to block ids.
+    pub groups: Vec<BlockGroup>,
+}
+
+impl Pack {
+    /// Loads the pack in `dir`, along with problems with individual blocks
+    /// that were left out.
+    pub fn load(dir: &Path) -> Result<(Self, Vec<PackError>), PackError> {
+        let mut manifest = PackManifest::load(&dir.join(MANIFEST_FILE))?;
+        if !is_valid_pack_id(&manifest.id) {
+            return Err(PackError::InvalidPackId(manifest.id));
+        }
+        let pack_id = manifest.id.clone();
+
+        let mut problems = Vec::new();
+        let mut blocks = BlockSet::default();
+        let mut order = Vec::with_capacity(manifest.blocks.len());
+        for id in std::mem::take(&mut manifest.blocks) {
+            if !is_valid_block_id(&id) {
+                problems.push(PackError::InvalidBlockId {
+                    pack: pack_id.clone(),
+                    block: id,
+                });
+                continue;
+            }
+            if order.contains(&id) {
+                problems.push(PackError::DuplicateBlock {
+                    pack: pack_id.clone(),
+                    block: id,
+                });
+                continue;
+            }
+            match load_block(dir, &pack_id, &id) {
+                Ok(block) => {
+                    blocks.insert(id.clone(), block);
+                    order.push(id);
+                }
+                Err(err) => problems.push(err),
+            }
+        }
+        manifest.blocks = order;
+
+        if blocks.get(&manifest.default_block).is_none() {
+            return Err(PackError::UnknownDefaultBlock {
+                pack: pack_id,
+                block: manifest.default_block,
+            });
+        }
+        manifest.block_info.retain(|id, _| {
+            let known = blocks.get(id).is_some();
+            if !known {
+                problems.push(PackError::UnknownBlockInfo {
+                    pack: pack_id.clone(),
+                    block: id.clone(),
+                });
+            }
+            known
+        });
+        let mut groups = Vec::with_capacity(manifest.groups.len());
+        for group in &mut manifest.groups {
+            let expansion = groups::expand(&group.blocks, &manifest.blocks);
+            problems.extend(expansion.problems.into_iter().map(|source| {
+                PackError::GroupSpecifier {
+                    pack: pack_id.clone(),
+                    group: group.name.clone(),
+                    source,
+                }
+            }));
+            group.blocks = expansion.specifiers;
+            groups.push(BlockGroup {
+                name: group.name.clone(),
+                blocks: expansion.blocks,
+            });
+        }
+
+        let pack = Self {
+            manifest,
+            dir: dir.to_owned(),
+            blocks,
+            groups,
+        };
+        Ok((pack, problems))
+    }
+
+    pub fn id(&self) -> &str {
+        &self.manifest.id
+    }
+
+    /// How a document drawn with this pack refers to it.
+    pub fn pack_ref(&self) -> BlockPackRef {
+        BlockPackRef {
+            id: self.manifest.id.clone(),
+            default_block: self.manifest.default_block.clone(),
+        }
+    }
+}
+
+/// The image file for block `id` in `dir`, if there is one.
+fn block_file(dir: &Path, id: &str) -> Option<(PathBuf, &'static str)> {
+    BLOCK_EXTENSIONS.iter().find_map(|&(extension, mime)| {
+        let path = dir.join(format!("{id}.{extension}"));
+        path.is_file().then_some((path, mime))
+    })
+}
+
+fn load_block(dir: &Path, pack: &str, id: &str) -> Result<Block, PackError> {
+    let (path, _) = block_file(dir, id).ok_or_else(|| PackError::MissingImage {
+        pack: pack.to_owned(),
+        block: id.to_owned(),
+    })?;
+    let block = Block::load(&path)?;
+    for frame in block.frames() {
+        let (width, height) = frame.image.dimensions();
+        if width == 0 || height == 0 || width > MAX_BLOCK_SIZE || height > MAX_BLOCK_SIZE {
+            return Err(PackError::BadDimensions {
+                path,
+                width,
+                height,
+            });
+        }
+    }
+    Ok(block)
+}
+
+/// Hidden folders hold packs that are still being unpacked.
+pub fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Every pack found, and what went wrong finding them.
+#[derive(Debug, Default)]
+pub struct PackLibrary {
+    packs: Vec<Pack>,
+    problems: Vec<Problem>,
+}
+
+/// A problem and the directory it was found in, so it can be forgotten when
+/// that directory is loaded again.
+#[derive(Debug)]
+struct Problem {
+    dir: PathBuf,
+    error: PackError,
+}
+
+impl PackLibrary {
+    /// Loads every pack in a subdirectory of one of `roots`. Roots that don't
+    /// exist and hidden subdirectories are skipped; when two packs share an
+    /// id, the first one found wins.
+    pub fn scan(roots: &[PathBuf]) -> Self {
+        let mut library = Self::default();
+        for root in roots {
+            let entries = match fs::read_dir(root) {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(source) => {
+                    library.problems.push(Problem {
+                        dir: root.clone(),
+                        error: PackError::Io {
+                            path: root.clone(),
+                            source,
+                        },
+                    });
+                    continue;
+                }
+            };
+            let mut dirs: Vec<PathBuf> = entries
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| !is_hidden(path) && path.join(MANIFEST_FILE).is_file())
+                .collect();
+            dirs.sort();
+            for dir in dirs {
+                library.add_dir(&dir);
+            }
+        }
+        library
+    }
+
+    fn add_dir(&mut self, dir: &Path) {
+        let mut problems = Vec::new();
+        match Pack::load(dir) {
+            Ok((pack, pack_problems)) => {
+                problems = pack_problems;
+                if self.get(pack.id()).is_some() {
+                    problems.push(PackError::DuplicatePack {
+                        pack: pack.manifest.id,
+                        path: dir.to_owned(),
+                    });
+                } else {
+                    self.packs.push(pack);
+                }
+            }
+            Err(err) => problems.push(err),
+        }
+        self.problems
+            .extend(problems.into_iter().map(|error| Problem {
+                dir: dir.to_owned(),
+                error,
+            }));
+    }
+
+    /// Loads the pack in `dir` again, replacing what was loaded from there
+    /// before; if `dir` no longer holds a pack, its old pack is dropped.
+    /// Returns the ids of the packs that were dropped or loaded.
+    pub fn reload(&mut self, dir: &Path) -> Vec<String> {
+        let mut changed = Vec::new();
+        self.packs.retain(|pack| {
+            let stale = pack.dir == dir;
+            if stale {
+                changed.push(pack.manifest.id.clone());
+            }
+            !stale
+        });
+        self.problems.retain(|problem| problem.dir != dir);
+
+        if dir.join(MANIFEST_FILE).is_file() {
+            self.add_dir(dir);
+            if let Some(pack) = self.packs.last().filter(|pack| pack.dir == dir) {
+                if !changed.contains(&pack.manifest.id) {
+                    changed.push(pack.manifest.id.clone());
+                }
+            }
+        }
+        changed
+    }
+
+    pub fn packs(&self) -> &[Pack] {
+        &self.packs
+    }
+
+    pub fn problems(&self) -> impl Iterator<Item = &PackError> {
+        self.problems.iter().map(|problem| &problem.error)
+    }
+
+    /// Problems found loading `dir`.
+    pub fn problems_in<'a>(&'a self, dir: &'a Path) -> impl Iterator<Item = &'a PackError> {
+        self.problems
+            .iter()
+            .filter(move |problem| problem.dir == dir)
+            .map(|problem| &problem.error)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Pack> {
+        self.packs.iter().find(|pack| pack.id() == id)
+    }
+
+    /// The blocks of pack `id`.
+    pub fn blocks(&self, id: &str) -> Result<&BlockSet, PackError> {
+        self.get(id)
+            .map(|pack| &pack.blocks)
+            .ok_or_else(|| PackError::UnknownPack(id.to_owned()))
+    }
+
+    /// Every pack with its images inlined as data URLs, for the frontend.
+    pub fn catalog(&self) -> PackCatalog {
+        let mut problems: Vec<String> = self.problems().map(ToString::to_string).collect();
+        let packs = self
+            .packs
+            .iter()
+            .map(|pack| {
+                let blocks = pack
+                    .manifest
+                    .blocks
+                    .iter()
+                    .filter_map(|id| match catalog_block(pack, id) {
+                        Ok(block) => Some(block),
+                        Err(err) => {
+                            problems.push(err.to_string());
+                            None
+                        }
+                    })
+                    .collect();
+                CatalogPack {
+                    id: pack.manifest.id.clone(),
+                    name: pack.manifest.name.clone(),
+                    default_block: pack.manifest.default_block.clone(),
+                    groups: pack.groups.clone(),
+                    blocks,
+                }
+            })
+            .collect();
+        PackCatalog { packs, problems }
+    }
+}
+
+fn catalog_block(pack: &Pack, id: &str) -> Result<CatalogBlock, PackError> {
+    let (path, mime) = block_file(&pack.dir, id).ok_or_else(|| PackError::MissingImage {
+        pack: pack.id().to_owned(),
+        block: id.to_owned(),
+    })?;
+    let bytes = fs::read(&path).map_err(|source| PackError::Io { path, source })?;
+    Ok(CatalogBlock {
+        id: id.to_owned(),
+        url: format!("data:{mime};base64,{}", BASE64.encode(bytes)),
+        animated: pack.blocks.get(id).is_some_and(Block::is_animated),
+        info: pack
+            .manifest
+            .block_info
+            .get(id)
+            .cloned()
+            .unwrap_or_default(),
+    })
+}
+
+/// The loaded packs as the frontend sees them.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct PackCatalog {
+    pub packs: Vec<CatalogPack>,
+    /// Packs and blocks that were skipped, as messages.
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct CatalogPack {
+    pub id: String,
+    pub name: String,
+    pub default_block: String,
+    pub groups: Vec<BlockGroup>,
+    pub blocks: Vec<CatalogBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct CatalogBlock {
+    pub id: String,
+    /// The image file as a `data:` URL.
+    pub url: String,
+    pub animated: bool,
+    #[serde(flatten)]
+    #[ts(flatten)]
+    pub info: BlockInfo,
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    /// A fresh empty directory under the system temp dir.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("binblock-packs-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_block(dir: &Path, id: &str, width: u32, height: u32) {
+        RgbaImage::from_pixel(width, height, Rgba([10, 20, 30, 255]))
+            .save(dir.join(format!("{id}.png")))
+            .unwrap();
+    }
+
+    fn write_manifest(dir: &Path, json: &str) {
+        fs::write(dir.join(MANIFEST_FILE), json).unwrap();
+    }
+
+    #[test]
+    fn loads_valid_blocks_and_reports_the_rest() {
+        let root = temp_dir("load");
+        let dir = root.join("demo");
+        fs::create_dir(&dir).unwrap();
+        write_block(&dir, "a", 4, 4);
+        write_block(&dir, "b", 4, 4);
+        write_block(&dir, "huge", MAX_BLOCK_SIZE + 1, 2);
+        write_manifest(
+            &dir,
+            r#"{
+                "id": "demo",
+                "name": "Demo",
+                "blocks": ["b", "a", "missing", "huge", "a", "../a"],
+                "groups": [{ "name": "main", "blocks": ["a", "huge"] }],
+                "blockInfo": {
+                    "a": { "name": "Ay", "tags": ["first"] },
+                    "missing": { "name": "Gone" }
+                },
+                "defaultBlock": "a"
+            }"#,
+        );
+
+        let library = PackLibrary::scan(&[root.clone(), root.join("nowhere")]);
+        let pack = library.get("demo").unwrap();
+        assert_eq!(pack.manifest.blocks, vec!["b", "a"]);
+        assert_eq!(pack.manifest.groups[0].blocks, vec!["a"]);
+        assert_eq!(pack.groups[0].blocks, vec!["a"]);
+        assert_eq!(library.blocks("demo").unwrap().len(), 2);
+        assert!(matches!(
+            library.blocks("other"),
+            Err(PackError::UnknownPack(_))
+        ));
+
+        let problems: Vec<_> = library.problems().map(ToString::to_string).collect();
+        assert_eq!(problems.len(), 6, "{problems:#?}");
+
+        let catalog = library.catalog();
+        assert_eq!(catalog.packs[0].blocks[0].id, "b");
+        assert_eq!(catalog.packs[0].blocks[1].info.name.as_deref(), Some("Ay"));
+        assert_eq!(catalog.packs[0].blocks[1].info.tags, vec!["first"]);
+        assert!(catalog.packs[0].blocks[0]
+            .url
+            .starts_with("data:image/png;base64,"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn skips_broken_and_duplicate_packs() {
+        let root = temp_dir("broken");
+        for (name, json) in [
+            (
+                "a-first",
+                r#"{"id": "same", "name": "First", "blocks": ["x"], "defaultBlock": "x"}"#,
+            ),
+            (
+                "b-second",
+                r#"{"id": "same", "name": "Second", "blocks": ["x"], "defaultBlock": "x"}"#,
+            ),
+            (
+                "c-no-default",
+                r#"{"id": "nodefault", "name": "No default", "blocks": ["x"], "defaultBlock": "y"}"#,
+            ),
+            (
+                "d-bad-id",
+                r#"{"id": "Bad Id", "name": "Bad", "blocks": ["x"], "defaultBlock": "x"}"#,
+            ),
+            ("e-not-json", "{"),
+        ] {
+            let dir = root.join(name);
+            fs::create_dir(&dir).unwrap();
+            write_block(&dir, "x", 2, 2);
+            write_manifest(&dir, json);
+        }
+
+        let library = PackLibrary::scan(std::slice::from_ref(&root));
+        let ids: Vec<_> = library.packs().iter().map(Pack::id).collect();
+        assert_eq!(ids, vec!["same"]);
+        assert_eq!(library.get("same").unwrap().manifest.name, "First");
+        assert!(matches!(
+            library.problems().collect::<Vec<_>>()[..],
+            [
+                PackError::DuplicatePack { .. },
+                PackError::UnknownDefaultBlock { .. },
+                PackError::InvalidPackId(_),
+                PackError::Manifest { .. },
+            ]
+        ));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn reloads_one_pack_at_a_time() {
+        let root = temp_dir("reload");
+        let dir = root.join("demo");
+        fs::create_dir(&dir).unwrap();
+        write_block(&dir, "a", 2, 2);
+        write_block(&dir, "b", 2, 2);
+        write_manifest(
+            &dir,
+            r#"{"id": "demo", "name": "Demo", "blocks": ["a", "b"], "defaultBlock": "a"}"#,
+        );
+        let mut library = PackLibrary::scan(std::slice::from_ref(&root));
+        assert_eq!(library.blocks("demo").unwrap().len(), 2);
+
+        fs::remove_file(dir.join("b.png")).unwrap();
+        assert_eq!(library.reload(&dir), vec!["demo"]);
+        assert_eq!(library.blocks("demo").unwrap().len(), 1);
+        assert_eq!(library.problems_in(&dir).count(), 1);
+
+        write_block(&dir, "b", 2, 2);
+        library.reload(&dir);
+        assert_eq!(library.blocks("demo").unwrap().len(), 2);
+        assert_eq!(library.problems().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(library.reload(&dir), vec!["demo"]);
+        assert!(library.packs().is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn reload_picks_up_edited_images() {
+        let root = temp_dir("edit");
+        let dir = root.join("demo");
+        fs::create_dir(&dir).unwrap();
+        write_block(&dir, "a", 2, 2);
+        write_manifest(
+            &dir,
+            r#"{"id": "demo", "name": "Demo", "blocks": ["a"], "defaultBlock": "a"}"#,
+        );
+        let mut library = PackLibrary::scan(std::slice::from_ref(&root));
+        let before = library.catalog().packs[0].blocks[0].url.clone();
+
+        RgbaImage::from_pixel(2, 2, Rgba([200, 0, 0, 255]))
+            .save(dir.join("a.png"))
+            .unwrap();
+        assert_eq!(library.reload(&dir), vec!["demo"]);
+        let block = library.blocks("demo").unwrap().get("a").unwrap();
+        assert_eq!(block.image().get_pixel(0, 0), &Rgba([200, 0, 0, 255]));
+        assert_ne!(library.catalog().packs[0].blocks[0].url, before);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
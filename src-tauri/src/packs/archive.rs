@@ -0,0 +1,524 @@
+//! `.binpack` files: a pack zipped up to hand to someone else. The archive is
+//! flat: `pack.json`, one image per block named as in the pack folder, and
+//! optionally `emoji.json` (an `EmojiProfile` for the pack's blocks) and a
+//! `LICENSE`. Installed packs keep those last two files in their folder, so
+//! a pack survives being passed along more than once.
+//!
+//! Entries are looked up by name, and a name that is anything but a plain
+//! file name fails the whole import rather than being skipped, since such an
+//! archive was made to write outside the pack folder.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use super::{
+    block_file, groups, is_valid_block_id, is_valid_pack_id, Pack, PackError, PackLibrary,
+    PackManifest, BLOCK_EXTENSIONS, MANIFEST_FILE,
+};
+use crate::emoji::EmojiProfile;
+
+pub const ARCHIVE_EXTENSION: &str = "binpack";
+
+pub const EMOJI_FILE: &str = "emoji.json";
+
+pub const LICENSE_FILE: &str = "LICENSE";
+
+/// Largest entry read from an archive, so a crafted file can't use up
+/// memory by claiming to unpack to gigabytes.
+const MAX_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Most entries in an archive, and most bytes they unpack to in total, for
+/// the same reason. Both are far beyond any real pack.
+const MAX_ENTRIES: usize = 10_000;
+const MAX_ARCHIVE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// The contents of a `.binpack`.
+#[derive(Debug, Clone)]
+pub struct PackArchive {
+    pub manifest: PackManifest,
+    /// File name and contents of each block's image, in manifest order.
+    pub images: Vec<(String, Vec<u8>)>,
+    pub emoji: Option<EmojiProfile>,
+    pub license: Option<String>,
+}
+
+impl PackArchive {
+    /// Loads the pack in `dir` afresh and collects it as `from_pack` does.
+    /// A pack with any problem is refused rather than shipped without the
+    /// blocks and groups `Pack::load` had to leave out of its manifest.
+    pub fn from_dir(dir: &Path, emoji: Option<&EmojiProfile>) -> Result<Self, PackError> {
+        let (pack, problems) = Pack::load(dir)?;
+        if let Some(problem) = problems.into_iter().next() {
+            return Err(problem);
+        }
+        Self::from_pack(&pack, emoji)
+    }
+
+    /// Collects `pack`'s blocks, with `emoji`'s entries for them as its emoji
+    /// map or, without one, the `emoji.json` in the pack's folder.
+    fn from_pack(pack: &Pack, emoji: Option<&EmojiProfile>) -> Result<Self, PackError> {
+        let read = |path: PathBuf| fs::read(&path).map_err(|source| PackError::Io { path, source });
+
+        let mut images = Vec::with_capacity(pack.manifest.blocks.len());
+        for id in &pack.manifest.blocks {
+            let (path, _) = block_file(&pack.dir, id).ok_or_else(|| PackError::MissingImage {
+                pack: pack.id().to_owned(),
+                block: id.clone(),
+            })?;
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            images.push((name, read(path)?));
+        }
+
+        let emoji = match emoji {
+            Some(profile) => {
+                let mut profile = profile.clone();
+                profile.emoji.retain(|id, _| pack.blocks.get(id).is_some());
+                Some(profile)
+            }
+            None => read_optional(&pack.dir.join(EMOJI_FILE))?
+                .map(|bytes| parse_emoji(&bytes))
+                .transpose()?,
+        };
+        let license = read_optional(&pack.dir.join(LICENSE_FILE))?
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+
+        Ok(Self {
+            manifest: pack.manifest.clone(),
+            images,
+            emoji,
+            license,
+        })
+    }
+
+    /// Reads a `.binpack`. The sizes the archive lists are checked before
+    /// anything is unpacked, and only the manifest and the entries it names
+    /// are read; the rest are passed on empty, for their names to be checked.
+    pub fn read(reader: impl Read + Seek) -> Result<Self, PackError> {
+        let mut archive = ZipArchive::new(reader)?;
+        if archive.len() > MAX_ENTRIES {
+            return Err(PackError::TooManyEntries(archive.len()));
+        }
+        let mut listed = Vec::with_capacity(archive.len());
+        for index in 0..archive.len() {
+            let file = archive.by_index(index)?;
+            listed.push((file.name().to_owned(), file.size()));
+        }
+        check_sizes(&listed)?;
+
+        let mut read_entry = |index: usize| -> Result<Vec<u8>, PackError> {
+            let file = archive.by_index(index)?;
+            let name = file.name().to_owned();
+            // Stop at the listed size, so the checked total holds even for
+            // entries that lie about it.
+            let size = file.size();
+            let mut bytes = Vec::with_capacity(size as usize);
+            file.take(size)
+                .read_to_end(&mut bytes)
+                .map_err(|source| PackError::Io {
+                    path: PathBuf::from(name),
+                    source,
+                })?;
+            Ok(bytes)
+        };
+        let mut manifest = None;
+        let mut wanted = BTreeSet::new();
+        if let Some(index) = listed.iter().position(|(name, _)| name == MANIFEST_FILE) {
+            let bytes = read_entry(index)?;
+            wanted = wanted_entries(&bytes);
+            manifest = Some((index, bytes));
+        }
+
+        let mut entries = Vec::with_capacity(listed.len());
+        for (index, (name, _)) in listed.into_iter().enumerate() {
+            let bytes = match manifest.take_if(|(manifest, _)| *manifest == index) {
+                Some((_, bytes)) => bytes,
+                None if wanted.contains(&name) => read_entry(index)?,
+                None => Vec::new(),
+            };
+            entries.push((name, bytes));
+        }
+        Self::from_entries(entries)
+    }
+
+    /// Checks an archive's entries and picks out the pack in them.
+    pub fn from_entries(entries: Vec<(String, Vec<u8>)>) -> Result<Self, PackError> {
+        let mut files = BTreeMap::new();
+        for (name, bytes) in entries {
+            if !is_plain_file_name(&name) {
+                return Err(PackError::UnsafeEntry(name));
+            }
+            if files.insert(name.clone(), bytes).is_some() {
+                return Err(PackError::DuplicateEntry(name));
+            }
+        }
+
+        let manifest_bytes = files
+            .remove(MANIFEST_FILE)
+            .ok_or(PackError::MissingManifest)?;
+        let manifest: PackManifest =
+            serde_json::from_slice(&manifest_bytes).map_err(|source| PackError::Manifest {
+                path: PathBuf::from(MANIFEST_FILE),
+                source,
+            })?;
+        check_manifest(&manifest)?;
+
+        let mut images = Vec::with_capacity(manifest.blocks.len());
+        for id in &manifest.blocks {
+            let image = BLOCK_EXTENSIONS.iter().find_map(|(extension, _)| {
+                let name = format!("{id}.{extension}");
+                files.remove(&name).map(|bytes| (name, bytes))
+            });
+            images.push(image.ok_or_else(|| PackError::MissingImage {
+                pack: manifest.id.clone(),
+                block: id.clone(),
+            })?);
+        }
+
+        let emoji = files
+            .remove(EMOJI_FILE)
+            .map(|bytes| parse_emoji(&bytes))
+            .transpose()?;
+        let license = files
+            .remove(LICENSE_FILE)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+
+        Ok(Self {
+            manifest,
+            images,
+            emoji,
+            license,
+        })
+    }
+
+    /// The files that make up the archive, by name.
+    pub fn entries(&self) -> Result<Vec<(String, Vec<u8>)>, PackError> {
+        let json_err = |path: &str| {
+            let path = PathBuf::from(path);
+            move |source| PackError::Manifest { path, source }
+        };
+        let mut entries = vec![(
+            MANIFEST_FILE.to_owned(),
+            serde_json::to_vec_pretty(&self.manifest).map_err(json_err(MANIFEST_FILE))?,
+        )];
+        entries.extend(self.images.iter().cloned());
+        if let Some(emoji) = &self.emoji {
+            entries.push((
+                EMOJI_FILE.to_owned(),
+                serde_json::to_vec_pretty(emoji).map_err(json_err(EMOJI_FILE))?,
+            ));
+        }
+        if let Some(license) = &self.license {
+            entries.push((LICENSE_FILE.to_owned(), license.clone().into_bytes()));
+        }
+        Ok(entries)
+    }
+
+    pub fn write(&self, writer: impl Write + Seek) -> Result<(), PackError> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(writer);
+        for (name, bytes) in self.entries()? {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(&bytes).map_err(|source| PackError::Io {
+                path: PathBuf::from(name),
+                source,
+            })?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Unpacks the pack into a new folder under `root`, named after its id.
+    /// Fails if `library` already has a pack with that id or the folder is
+    /// taken. The files are checked in a hidden folder first, so a pack with
+    /// bad images is never installed halfway.
+    pub fn install(&self, root: &Path, library: &PackLibrary) -> Result<PathBuf, PackError> {
+        let id = &self.manifest.id;
+        let dir = root.join(id);
+        if library.get(id).is_some() || dir.exists() {
+            return Err(PackError::PackExists(id.clone()));
+        }
+
+        let staging = root.join(format!(".{id}.partial"));
+        let io_err = |path: &Path| {
+            let path = path.to_owned();
+            move |source| PackError::Io { path, source }
+        };
+        match fs::remove_dir_all(&staging) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                return Err(io_err(&staging)(err));
+            }
+            _ => {}
+        }
+        fs::create_dir_all(&staging).map_err(io_err(&staging))?;
+
+        let unpacked = self.entries().and_then(|entries| {
+            for (name, bytes) in entries {
+                let path = staging.join(name);
+                fs::write(&path, bytes).map_err(io_err(&path))?;
+            }
+            match Pack::load(&staging)? {
+                (_, problems) if !problems.is_empty() => {
+                    Err(problems.into_iter().next().expect("problems is not empty"))
+                }
+                _ => Ok(()),
+            }
+        });
+        if let Err(err) = unpacked {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(err);
+        }
+        fs::rename(&staging, &dir).map_err(io_err(&dir))?;
+        Ok(dir)
+    }
+}
+
+/// Whether `name` is a bare file name that can't lead outside the folder it
+/// is joined onto, on any platform.
+fn is_plain_file_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', ':', '\0'])
+}
+
+/// An archive has to be complete to be installed, so unlike `Pack::load`
+/// nothing here is dropped with a warning.
+fn check_manifest(manifest: &PackManifest) -> Result<(), PackError> {
+    let pack = || manifest.id.clone();
+    if !is_valid_pack_id(&manifest.id) {
+        return Err(PackError::InvalidPackId(pack()));
+    }
+    for (index, id) in manifest.blocks.iter().enumerate() {
+        if !is_valid_block_id(id) {
+            return Err(PackError::InvalidBlockId {
+                pack: pack(),
+                block: id.clone(),
+            });
+        }
+        if manifest.blocks[..index].contains(id) {
+            return Err(PackError::DuplicateBlock {
+                pack: pack(),
+                block: id.clone(),
+            });
+        }
+    }
+    if !manifest.blocks.contains(&manifest.default_block) {
+        return Err(PackError::UnknownDefaultBlock {
+            pack: pack(),
+            block: manifest.default_block.clone(),
+        });
+    }
+    if let Some(id) = manifest
+        .block_info
+        .keys()
+        .find(|id| !manifest.blocks.contains(id))
+    {
+        return Err(PackError::UnknownBlockInfo {
+            pack: pack(),
+            block: id.clone(),
+        });
+    }
+    for group in &manifest.groups {
+        let expansion = groups::expand(&group.blocks, &manifest.blocks);
+        if let Some(source) = expansion.problems.into_iter().next() {
+            return Err(PackError::GroupSpecifier {
+                pack: pack(),
+                group: group.name.clone(),
+                source,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks the entry sizes an archive lists, before any are unpacked.
+fn check_sizes(listed: &[(String, u64)]) -> Result<(), PackError> {
+    let mut total = 0u64;
+    for (name, size) in listed {
+        if *size > MAX_ENTRY_SIZE {
+            return Err(PackError::EntryTooLarge(name.clone()));
+        }
+        total += size;
+        if total > MAX_ARCHIVE_SIZE {
+            return Err(PackError::ArchiveTooLarge);
+        }
+    }
+    Ok(())
+}
+
+/// Names of the entries `from_entries` could use, going by the manifest in
+/// `manifest`. A manifest that doesn't parse wants nothing, and is reported
+/// by `from_entries`.
+fn wanted_entries(manifest: &[u8]) -> BTreeSet<String> {
+    let Ok(manifest) = serde_json::from_slice::<PackManifest>(manifest) else {
+        return BTreeSet::new();
+    };
+    let images = manifest.blocks.iter().flat_map(|id| {
+        BLOCK_EXTENSIONS
+            .iter()
+            .map(move |(extension, _)| format!("{id}.{extension}"))
+    });
+    [EMOJI_FILE, LICENSE_FILE]
+        .map(str::to_owned)
+        .into_iter()
+        .chain(images)
+        .collect()
+}
+
+fn parse_emoji(bytes: &[u8]) -> Result<EmojiProfile, PackError> {
+    serde_json::from_slice(bytes).map_err(PackError::EmojiMap)
+}
+
+fn read_optional(path: &Path) -> Result<Option<Vec<u8>>, PackError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(PackError::Io {
+            path: path.to_owned(),
+            source,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    fn png(value: u8) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        RgbaImage::from_pixel(2, 2, Rgba([value, value, value, 255]))
+            .write_to(&mut io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    fn archive_entries(manifest: &str) -> Vec<(String, Vec<u8>)> {
+        vec![
+            (MANIFEST_FILE.to_owned(), manifest.as_bytes().to_vec()),
+            ("a.png".to_owned(), png(10)),
+            ("b.png".to_owned(), png(200)),
+            (
+                EMOJI_FILE.to_owned(),
+                br#"{"emoji": {"a": "<:shared_a:123>"}}"#.to_vec(),
+            ),
+            ("notes.txt".to_owned(), b"ignored".to_vec()),
+        ]
+    }
+
+    const MANIFEST: &str =
+        r#"{"id": "shared", "name": "Shared", "blocks": ["a", "b"], "defaultBlock": "a"}"#;
+
+    #[test]
+    fn rejects_entries_outside_the_pack() {
+        for name in [
+            "../evil.png",
+            "sub/a.png",
+            "/etc/passwd",
+            "C:evil",
+            "..\\evil",
+            ".hidden",
+            "",
+        ] {
+            let mut entries = archive_entries(MANIFEST);
+            entries.push((name.to_owned(), png(0)));
+            assert!(
+                matches!(
+                    PackArchive::from_entries(entries),
+                    Err(PackError::UnsafeEntry(entry)) if entry == name
+                ),
+                "{name:?}"
+            );
+        }
+
+        let mut entries = archive_entries(MANIFEST);
+        entries.push(("a.png".to_owned(), png(0)));
+        assert!(matches!(
+            PackArchive::from_entries(entries),
+            Err(PackError::DuplicateEntry(_))
+        ));
+        assert!(matches!(
+            PackArchive::from_entries(archive_entries(
+                r#"{"id": "shared", "name": "Shared", "blocks": ["a", "c"], "defaultBlock": "a"}"#
+            )),
+            Err(PackError::MissingImage { .. })
+        ));
+    }
+
+    #[test]
+    fn checks_listed_sizes_before_unpacking() {
+        let entry = |name: &str, size: u64| (name.to_owned(), size);
+        assert!(check_sizes(&[entry("a.png", 10), entry("b.png", MAX_ENTRY_SIZE)]).is_ok());
+        assert!(matches!(
+            check_sizes(&[entry("a.png", MAX_ENTRY_SIZE + 1)]),
+            Err(PackError::EntryTooLarge(name)) if name == "a.png"
+        ));
+        let many: Vec<_> = (0..=MAX_ARCHIVE_SIZE / MAX_ENTRY_SIZE)
+            .map(|index| entry(&format!("{index}.png"), MAX_ENTRY_SIZE))
+            .collect();
+        assert!(matches!(
+            check_sizes(&many),
+            Err(PackError::ArchiveTooLarge)
+        ));
+    }
+
+    #[test]
+    fn only_wants_entries_the_manifest_can_use() {
+        let wanted = wanted_entries(MANIFEST.as_bytes());
+        for name in ["a.png", "b.gif", EMOJI_FILE, LICENSE_FILE] {
+            assert!(wanted.contains(name), "{name}");
+        }
+        assert!(!wanted.contains("notes.txt"));
+        assert!(!wanted.contains("c.png"));
+        assert!(wanted_entries(b"{").is_empty());
+    }
+
+    #[test]
+    fn installs_once_and_round_trips() {
+        let root = std::env::temp_dir().join(format!("binblock-archive-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let archive = PackArchive::from_entries(archive_entries(MANIFEST)).unwrap();
+        assert_eq!(archive.images.len(), 2);
+        assert_eq!(archive.license, None);
+
+        let dir = archive.install(&root, &PackLibrary::default()).unwrap();
+        assert_eq!(dir, root.join("shared"));
+        assert!(matches!(
+            archive.install(&root, &PackLibrary::default()),
+            Err(PackError::PackExists(_))
+        ));
+
+        let library = PackLibrary::scan(std::slice::from_ref(&root));
+        let pack = library.get("shared").unwrap();
+        assert_eq!(library.problems().count(), 0);
+        let again = PackArchive::from_dir(&pack.dir, None).unwrap();
+        assert_eq!(again.manifest, archive.manifest);
+        assert_eq!(again.images, archive.images);
+        assert_eq!(again.emoji, archive.emoji);
+
+        fs::write(pack.dir.join("b.png"), b"not a png").unwrap();
+        assert!(matches!(
+            PackArchive::from_dir(&pack.dir, None),
+            Err(PackError::Block(_))
+        ));
+
+        let mut broken = archive.clone();
+        broken.manifest.id = "broken".to_owned();
+        broken.images[1].1 = b"not a png".to_vec();
+        assert!(matches!(
+            broken.install(&root, &library),
+            Err(PackError::Block(_))
+        ));
+        assert!(!root.join("broken").exists());
+        assert!(!root.join(".broken.partial").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+//! Block group specifiers. A group in `pack.json` lists specifiers rather
+//! than every id:
+//!
+//! - `12` names one block;
+//! - `00..12` is every number from 00 to 12, kept as wide as the ends;
+//! - `col_*_hi` is a glob, with `*`, `?` and `[...]`;
+//! - `/col_(red|blue)_.*/` is a regex that has to match the whole id;
+//! - `!` in front of any of these takes its blocks back out of the group.
+//!
+//! Specifiers are applied in order, and the group lists blocks in the order
+//! they were added; globs and regexes add them in palette order.
+
+use glob::Pattern;
+use regex::Regex;
+
+/// More numbers than this in a range is taken as a typo.
+const MAX_RANGE_LEN: u32 = 10_000;
+
+/// Why a specifier was dropped from its group. Worded to follow the group's
+/// name in `PackError::GroupSpecifier`.
+#[derive(Debug, thiserror::Error)]
+pub enum SpecifierError {
+    #[error("names unknown block {0:?}")]
+    UnknownBlock(String),
+    #[error("has invalid range {0:?}; use two numbers like 00..12, smallest first")]
+    BadRange(String),
+    #[error("has invalid glob {specifier:?}: {source}")]
+    BadGlob {
+        specifier: String,
+        source: glob::PatternError,
+    },
+    #[error("has invalid regex {specifier:?}: {source}")]
+    BadRegex {
+        specifier: String,
+        source: regex::Error,
+    },
+    #[error("has {0:?}, which matches no blocks")]
+    NoMatch(String),
+}
+
+#[derive(Debug)]
+enum Matcher {
+    Id(String),
+    Range { start: u32, end: u32, width: usize },
+    Glob(Pattern),
+    Regex(Regex),
+}
+
+#[derive(Debug)]
+struct Specifier {
+    exclude: bool,
+    matcher: Matcher,
+}
+
+impl Specifier {
+    fn parse(specifier: &str) -> Result<Self, SpecifierError> {
+        let (exclude, body) = match specifier.strip_prefix('!') {
+            Some(body) => (true, body),
+            None => (false, specifier),
+        };
+
+        let matcher = if let Some(regex) = body
+            .strip_prefix('/')
+            .and_then(|body| body.strip_suffix('/'))
+        {
+            Regex::new(&format!("^(?:{regex})$"))
+                .map(Matcher::Regex)
+                .map_err(|source| SpecifierError::BadRegex {
+                    specifier: specifier.to_owned(),
+                    source,
+                })?
+        } else if let Some((start, end)) = body.split_once("..") {
+            parse_range(start, end).ok_or_else(|| SpecifierError::BadRange(specifier.to_owned()))?
+        } else if body.contains(['*', '?', '[']) {
+            Pattern::new(body)
+                .map(Matcher::Glob)
+                .map_err(|source| SpecifierError::BadGlob {
+                    specifier: specifier.to_owned(),
+                    source,
+                })?
+        } else {
+            Matcher::Id(body.to_owned())
+        };
+        Ok(Self { exclude, matcher })
+    }
+
+    /// The blocks of `ids` this specifier picks out.
+    fn matches<'a>(&self, ids: &'a [String]) -> Vec<&'a str> {
+        let known = |id: &str| ids.iter().find(|known| *known == id).map(String::as_str);
+        match &self.matcher {
+            Matcher::Id(id) => known(id).into_iter().collect(),
+            Matcher::Range { start, end, width } => (*start..=*end)
+                .filter_map(|n| known(&format!("{n:0width$}")))
+                .collect(),
+            Matcher::Glob(pattern) => ids
+                .iter()
+                .filter(|id| pattern.matches(id))
+                .map(String::as_str)
+                .collect(),
+            Matcher::Regex(regex) => ids
+                .iter()
+                .filter(|id| regex.is_match(id))
+                .map(String::as_str)
+                .collect(),
+        }
+    }
+}
+
+/// Both ends are plain numbers, smallest first. Ends of the same length pad
+/// every number to it, so `00..12` gives `00`, `01` and so on; otherwise
+/// `0..12` gives `0` to `12` unpadded.
+fn parse_range(start: &str, end: &str) -> Option<Matcher> {
+    let number = |s: &str| {
+        (!s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| s.parse::<u32>().ok())
+            .flatten()
+    };
+    let (first, last) = (number(start)?, number(end)?);
+    if first > last || last - first >= MAX_RANGE_LEN {
+        return None;
+    }
+    Some(Matcher::Range {
+        start: first,
+        end: last,
+        width: if start.len() == end.len() {
+            start.len()
+        } else {
+            0
+        },
+    })
+}
+
+/// A group's specifiers applied to a pack.
+#[derive(Debug, Default)]
+pub struct Expansion {
+    /// The group's blocks, in order.
+    pub blocks: Vec<String>,
+    /// The specifiers that parsed and matched something.
+    pub specifiers: Vec<String>,
+    pub problems: Vec<SpecifierError>,
+}
+
+/// Applies `specifiers` to `ids`, a pack's blocks in palette order. Invalid
+/// specifiers and those that match nothing are left out and reported.
+pub fn expand(specifiers: &[String], ids: &[String]) -> Expansion {
+    let mut expansion = Expansion::default();
+    for text in specifiers {
+        let specifier = match Specifier::parse(text) {
+            Ok(specifier) => specifier,
+            Err(err) => {
+                expansion.problems.push(err);
+                continue;
+            }
+        };
+        let matches = specifier.matches(ids);
+        if matches.is_empty() {
+            expansion.problems.push(match specifier.matcher {
+                Matcher::Id(id) => SpecifierError::UnknownBlock(id),
+                _ => SpecifierError::NoMatch(text.clone()),
+            });
+            continue;
+        }
+
+        if specifier.exclude {
+            expansion
+                .blocks
+                .retain(|id| !matches.contains(&id.as_str()));
+        } else {
+            for id in matches {
+                if !expansion.blocks.iter().any(|block| block == id) {
+                    expansion.blocks.push(id.to_owned());
+                }
+            }
+        }
+        expansion.specifiers.push(text.clone());
+    }
+    expansion
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn expands_ranges_globs_regexes_and_exclusions() {
+        let pack = ids(&[
+            "00",
+            "01",
+            "02",
+            "10",
+            "12",
+            "col_red_hi",
+            "col_red_lo",
+            "col_blue_hi",
+            "wave",
+        ]);
+        let expand = |specifiers: &[&str]| expand(&ids(specifiers), &pack);
+
+        assert_eq!(
+            expand(&["00..12"]).blocks,
+            ids(&["00", "01", "02", "10", "12"])
+        );
+        assert_eq!(expand(&["1..12"]).blocks, ids(&["10", "12"]));
+        assert_eq!(
+            expand(&["wave", "col_*_hi"]).blocks,
+            ids(&["wave", "col_red_hi", "col_blue_hi"])
+        );
+        assert_eq!(
+            expand(&["/col_red_.*/", "00", "!col_red_lo"]).blocks,
+            ids(&["col_red_hi", "00"])
+        );
+        assert_eq!(
+            expand(&["00..12", "!/1./", "01"]).blocks,
+            ids(&["00", "01", "02"])
+        );
+
+        let expansion = expand(&[
+            "00..02",
+            "12..00",
+            "col_[",
+            "/(/",
+            "nope",
+            "col_*_mid",
+            "!x*",
+        ]);
+        assert_eq!(expansion.blocks, ids(&["00", "01", "02"]));
+        assert_eq!(expansion.specifiers, ids(&["00..02"]));
+        assert!(matches!(
+            &expansion.problems[..],
+            [
+                SpecifierError::BadRange(_),
+                SpecifierError::BadGlob { .. },
+                SpecifierError::BadRegex { .. },
+                SpecifierError::UnknownBlock(id),
+                SpecifierError::NoMatch(glob),
+                SpecifierError::NoMatch(excluded),
+            ] if id == "nope" && glob == "col_*_mid" && excluded == "!x*"
+        ));
+    }
+}
@@ -0,0 +1,232 @@
+//! Turning pictures into blocks in the user's own pack. Each picture is
+//! cropped to its centre square and resampled to `IMPORTED_BLOCK_SIZE`, then
+//! written back out as a fresh PNG, which drops EXIF data, text chunks and
+//! colour profiles. Animated GIFs keep only their first frame.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+
+use super::{block_file, PackError, PackManifest, MANIFEST_FILE};
+use crate::project::write_atomic;
+
+/// Id of the pack imported blocks go into, in the user's packs directory.
+pub const USER_PACK_ID: &str = "custom";
+
+const USER_PACK_NAME: &str = "Custom blocks";
+
+/// Side length of imported blocks, the same as the set-2 blocks.
+pub const IMPORTED_BLOCK_SIZE: u32 = 64;
+
+/// Crops `image` to its centre square and resamples it to
+/// `IMPORTED_BLOCK_SIZE`. Small pictures are scaled up without smoothing so
+/// pixel art stays sharp.
+pub fn normalize(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let side = width.min(height);
+    let square = imageops::crop_imm(image, (width - side) / 2, (height - side) / 2, side, side);
+    let filter = if side < IMPORTED_BLOCK_SIZE {
+        FilterType::Nearest
+    } else {
+        FilterType::Lanczos3
+    };
+    imageops::resize(&*square, IMPORTED_BLOCK_SIZE, IMPORTED_BLOCK_SIZE, filter)
+}
+
+/// A block id made from `path`'s file name: lowercase, with runs of anything
+/// but letters and digits turned into single hyphens.
+fn base_id(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let id = stem
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if id.is_empty() {
+        "block".to_owned()
+    } else {
+        id
+    }
+}
+
+/// `base`, or `base-2`, `base-3` and so on, whichever is first not `taken`.
+fn unique_id(base: &str, taken: impl Fn(&str) -> bool) -> String {
+    let mut id = base.to_owned();
+    let mut n = 1;
+    while taken(&id) {
+        n += 1;
+        id = format!("{base}-{n}");
+    }
+    id
+}
+
+/// Adds the pictures at `paths` as new blocks in the pack in `dir`, creating
+/// the pack if it doesn't exist yet, and returns their ids in the same order.
+/// Every picture is read before anything is written, and if writing the
+/// blocks or the manifest fails, the images already written are removed
+/// again, so a failed import leaves the pack as it was.
+pub fn import_blocks(dir: &Path, paths: &[PathBuf]) -> Result<Vec<String>, PackError> {
+    let images = paths
+        .iter()
+        .map(|path| {
+            let image = image::open(path).map_err(|source| PackError::Image {
+                path: path.clone(),
+                source,
+            })?;
+            Ok((base_id(path), normalize(&image.into_rgba8())))
+        })
+        .collect::<Result<Vec<_>, PackError>>()?;
+
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let manifest = match PackManifest::load(&manifest_path) {
+        Ok(manifest) => manifest,
+        Err(PackError::Io { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+            PackManifest {
+                id: USER_PACK_ID.to_owned(),
+                name: USER_PACK_NAME.to_owned(),
+                blocks: Vec::new(),
+                groups: Vec::new(),
+                block_info: BTreeMap::new(),
+                default_block: String::new(),
+            }
+        }
+        Err(err) => return Err(err),
+    };
+    fs::create_dir_all(dir).map_err(io_err(dir))?;
+
+    let mut written = Vec::with_capacity(images.len());
+    let result = add_blocks(dir, manifest, images, &mut written);
+    if result.is_err() {
+        for path in written.iter().filter(|path| path.is_file()) {
+            if let Err(err) = fs::remove_file(path) {
+                eprintln!("failed to remove {}: {err}", path.display());
+            }
+        }
+    }
+    result
+}
+
+/// Writes `images` into `dir` as new blocks of `manifest`, then the updated
+/// manifest, noting each image file in `written` before it's created, since
+/// a failed save can leave part of one behind.
+fn add_blocks(
+    dir: &Path,
+    mut manifest: PackManifest,
+    images: Vec<(String, RgbaImage)>,
+    written: &mut Vec<PathBuf>,
+) -> Result<Vec<String>, PackError> {
+    let mut ids = Vec::with_capacity(images.len());
+    for (base, image) in images {
+        let id = unique_id(&base, |id| {
+            manifest.blocks.iter().any(|block| block == id) || block_file(dir, id).is_some()
+        });
+        let path = dir.join(format!("{id}.png"));
+        written.push(path.clone());
+        image
+            .save(&path)
+            .map_err(|source| PackError::Image { path, source })?;
+        manifest.blocks.push(id.clone());
+        ids.push(id);
+    }
+
+    if !manifest.blocks.contains(&manifest.default_block) {
+        if let Some(first) = manifest.blocks.first() {
+            manifest.default_block = first.clone();
+        }
+    }
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|source| PackError::Manifest {
+        path: manifest_path.clone(),
+        source,
+    })?;
+    write_atomic(&manifest_path, &json).map_err(io_err(&manifest_path))?;
+    Ok(ids)
+}
+
+fn io_err(path: &Path) -> impl FnOnce(io::Error) -> PackError {
+    let path = path.to_owned();
+    move |source| PackError::Io { path, source }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::super::Pack;
+    use super::*;
+
+    #[test]
+    fn imports_cropped_blocks_with_unique_ids() {
+        let root = std::env::temp_dir().join(format!("binblock-import-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        // Red on the left and right edges, green in the centre square.
+        let wide = RgbaImage::from_fn(6, 4, |x, _| {
+            if (1..5).contains(&x) {
+                Rgba([0, 255, 0, 255])
+            } else {
+                Rgba([255, 0, 0, 255])
+            }
+        });
+        let picture = root.join("My Photo!.png");
+        wide.save(&picture).unwrap();
+
+        let dir = root.join(USER_PACK_ID);
+        let ids = import_blocks(&dir, &[picture.clone(), picture.clone()]).unwrap();
+        assert_eq!(ids, vec!["my-photo", "my-photo-2"]);
+        let ids = import_blocks(&dir, std::slice::from_ref(&picture)).unwrap();
+        assert_eq!(ids, vec!["my-photo-3"]);
+        assert!(matches!(
+            import_blocks(&dir, &[root.join("missing.png")]),
+            Err(PackError::Image { .. })
+        ));
+
+        let (pack, problems) = Pack::load(&dir).unwrap();
+        assert!(problems.is_empty(), "{problems:?}");
+        assert_eq!(pack.id(), USER_PACK_ID);
+        assert_eq!(
+            pack.manifest.blocks,
+            vec!["my-photo", "my-photo-2", "my-photo-3"]
+        );
+        assert_eq!(pack.manifest.default_block, "my-photo");
+        let image = pack.blocks.get("my-photo").unwrap().image();
+        assert_eq!(
+            image.dimensions(),
+            (IMPORTED_BLOCK_SIZE, IMPORTED_BLOCK_SIZE)
+        );
+        assert_eq!(image[(0, 0)], Rgba([0, 255, 0, 255]));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn failed_writes_leave_the_pack_as_it_was() {
+        let root =
+            std::env::temp_dir().join(format!("binblock-import-fail-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let dir = root.join(USER_PACK_ID);
+        fs::create_dir_all(&dir).unwrap();
+        let pictures = ["a", "b"].map(|name| {
+            let path = root.join(format!("{name}.png"));
+            RgbaImage::new(2, 2).save(&path).unwrap();
+            path
+        });
+
+        // `b.png` can't be written over a directory, after `a.png` was.
+        fs::create_dir(dir.join("b.png")).unwrap();
+        assert!(matches!(
+            import_blocks(&dir, &pictures),
+            Err(PackError::Image { .. })
+        ));
+        assert!(!dir.join("a.png").exists());
+        assert!(!dir.join(MANIFEST_FILE).exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
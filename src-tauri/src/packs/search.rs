@@ -0,0 +1,220 @@
+//! Fuzzy search over blocks' ids, names, tags and aliases, so the palette can
+//! filter packs with hundreds of blocks. A query is split on whitespace and
+//! every word has to turn up in one of a block's fields, its letters in
+//! order but not necessarily next to each other: `hrip` finds
+//! `Horizontal_Ripple_0264x64`.
+
+use std::cmp::Reverse;
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use super::{Pack, PackLibrary};
+
+/// Scores for how a word matched a field; fuzzy matches always rank below
+/// substrings, which rank below prefixes and whole matches.
+const EXACT: u32 = 1000;
+const PREFIX: u32 = 600;
+const SUBSTRING: u32 = 400;
+const MAX_FUZZY: u32 = SUBSTRING - 1;
+
+/// Bonuses for each letter of a fuzzy match.
+const LETTER: u32 = 4;
+const CONSECUTIVE: u32 = 8;
+const WORD_START: u32 = 12;
+
+/// Names, ids and aliases are what people search for, so they count for
+/// more than tags, which many blocks share.
+const NAME_WEIGHT: u32 = 2;
+const TAG_WEIGHT: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BlockMatch {
+    pub pack: String,
+    pub id: String,
+    /// Higher is a better match.
+    pub score: u32,
+}
+
+/// How well `word` matches `text`, ignoring case, or `None` if its letters
+/// can't all be found in order.
+pub fn fuzzy_score(word: &str, text: &str) -> Option<u32> {
+    let word = word.to_lowercase();
+    let lower = text.to_lowercase();
+    if word.is_empty() {
+        return Some(0);
+    }
+    if lower == word {
+        return Some(EXACT);
+    }
+    if lower.starts_with(&word) {
+        return Some(PREFIX);
+    }
+    if let Some(index) = lower.find(&word) {
+        let at_word_start = lower[..index]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        return Some(SUBSTRING + if at_word_start { WORD_START } else { 0 });
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+    for wanted in word.chars() {
+        let index = (next..chars.len()).find(|&i| chars[i].to_lowercase().eq([wanted]))?;
+        score += LETTER;
+        if last_match.is_some_and(|last| last + 1 == index) {
+            score += CONSECUTIVE;
+        }
+        if is_word_start(&chars, index) {
+            score += WORD_START;
+        }
+        last_match = Some(index);
+        next = index + 1;
+    }
+    Some(score.min(MAX_FUZZY))
+}
+
+/// The start of the text, of a word after punctuation or a digit run, or of
+/// a capitalised word in camelCase.
+fn is_word_start(chars: &[char], index: usize) -> bool {
+    let Some(&previous) = index.checked_sub(1).and_then(|i| chars.get(i)) else {
+        return true;
+    };
+    let current = chars[index];
+    !previous.is_alphanumeric()
+        || (previous.is_lowercase() && current.is_uppercase())
+        || (previous.is_alphabetic() != current.is_alphabetic())
+}
+
+/// How well every word of `query` matches block `id` in `pack`, or `None` if
+/// some word matches nothing.
+fn block_score(pack: &Pack, id: &str, words: &[&str]) -> Option<u32> {
+    let info = pack.manifest.block_info.get(id);
+    let names = std::iter::once(id)
+        .chain(info.and_then(|info| info.name.as_deref()))
+        .chain(
+            info.into_iter()
+                .flat_map(|info| info.aliases.iter().map(String::as_str)),
+        );
+    let tags = info
+        .into_iter()
+        .flat_map(|info| info.tags.iter().map(String::as_str));
+
+    words.iter().try_fold(0, |total, word| {
+        let best = names
+            .clone()
+            .filter_map(|name| fuzzy_score(word, name))
+            .map(|score| score * NAME_WEIGHT)
+            .chain(
+                tags.clone()
+                    .filter_map(|tag| fuzzy_score(word, tag))
+                    .map(|score| score * TAG_WEIGHT),
+            )
+            .max()?;
+        Some(total + best)
+    })
+}
+
+/// Blocks matching `query`, best first, from the pack with id `pack` or from
+/// every pack. Ties keep palette order, so an empty query lists every block.
+pub fn search(library: &PackLibrary, query: &str, pack: Option<&str>) -> Vec<BlockMatch> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let mut matches: Vec<BlockMatch> = library
+        .packs()
+        .iter()
+        .filter(|candidate| pack.is_none_or(|id| candidate.id() == id))
+        .flat_map(|pack| {
+            let words = &words;
+            pack.manifest.blocks.iter().filter_map(move |id| {
+                Some(BlockMatch {
+                    pack: pack.id().to_owned(),
+                    id: id.clone(),
+                    score: block_score(pack, id, words)?,
+                })
+            })
+        })
+        .collect();
+    matches.sort_by_key(|m| Reverse(m.score));
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use super::super::{BlockInfo, PackManifest};
+    use super::*;
+    use crate::blocks::BlockSet;
+
+    #[test]
+    fn ranks_whole_words_above_scattered_letters() {
+        assert_eq!(fuzzy_score("ripple", "ripple"), Some(EXACT));
+        assert_eq!(fuzzy_score("RIP", "Ripple"), Some(PREFIX));
+        assert_eq!(
+            fuzzy_score("ripple", "Horizontal_Ripple_02"),
+            Some(SUBSTRING + WORD_START)
+        );
+        assert_eq!(
+            fuzzy_score("ipple", "Horizontal_Ripple_02"),
+            Some(SUBSTRING)
+        );
+        assert_eq!(fuzzy_score("hrx", "Horizontal_Ripple_02"), None);
+
+        let scattered = fuzzy_score("hrip", "Horizontal_Ripple_0264x64").unwrap();
+        let buried = fuzzy_score("orpl", "Horizontal_Ripple_0264x64").unwrap();
+        assert!(scattered > buried, "{scattered} <= {buried}");
+        assert!(scattered < SUBSTRING);
+    }
+
+    #[test]
+    fn searches_names_tags_and_aliases() {
+        let info = |name: Option<&str>, tags: &[&str], aliases: &[&str]| BlockInfo {
+            name: name.map(str::to_owned),
+            tags: tags.iter().map(|s| s.to_string()).collect(),
+            aliases: aliases.iter().map(|s| s.to_string()).collect(),
+        };
+        let manifest = PackManifest {
+            id: "demo".into(),
+            name: "Demo".into(),
+            blocks: vec!["6811164".into(), "col_blue_hi".into(), "wave".into()],
+            groups: Vec::new(),
+            block_info: BTreeMap::from([
+                ("6811164".into(), info(Some("Brick wall"), &["red"], &[])),
+                (
+                    "col_blue_hi".into(),
+                    info(None, &["blue", "bright"], &["sky"]),
+                ),
+                ("wave".into(), info(Some("Blue wave"), &["water"], &[])),
+            ]),
+            default_block: "wave".into(),
+        };
+        let library = PackLibrary {
+            packs: vec![Pack {
+                manifest,
+                dir: PathBuf::new(),
+                blocks: BlockSet::default(),
+                groups: Vec::new(),
+            }],
+            problems: Vec::new(),
+        };
+        let ids = |query: &str| -> Vec<String> {
+            search(&library, query, None)
+                .into_iter()
+                .map(|m| m.id)
+                .collect()
+        };
+
+        assert_eq!(ids("brick"), vec!["6811164"]);
+        assert_eq!(ids("sky"), vec!["col_blue_hi"]);
+        assert_eq!(ids("blue"), vec!["wave", "col_blue_hi"]);
+        assert_eq!(ids("blue water"), vec!["wave"]);
+        assert_eq!(ids(""), vec!["6811164", "col_blue_hi", "wave"]);
+        assert!(ids("nothing").is_empty());
+        assert!(search(&library, "", Some("other")).is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+//! Deciding which packs to reload when files under the user's packs
+//! directory change. Editors and image tools often write a file several
+//! times in a row, so changes are collected until things have been quiet for
+//! `DEBOUNCE` and each touched pack is then reloaded once.
+
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use ts_rs::TS;
+
+use super::is_hidden;
+
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Payload of the `blocks-changed` event.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct BlocksChanged {
+    /// Ids of the packs that were reloaded, added or removed.
+    pub packs: Vec<String>,
+}
+
+/// The pack directory directly under `root` that `path` is in, unless it's
+/// a hidden one.
+pub fn pack_dir(root: &Path, path: &Path) -> Option<PathBuf> {
+    match path.strip_prefix(root).ok()?.components().next()? {
+        Component::Normal(name) => Some(root.join(name)).filter(|dir| !is_hidden(dir)),
+        _ => None,
+    }
+}
+
+/// Pack directories with changes waiting to be reloaded.
+#[derive(Debug, Default)]
+pub struct Debounce {
+    dirs: BTreeSet<PathBuf>,
+    last_change: Option<Instant>,
+}
+
+impl Debounce {
+    /// Notes that `dir` changed at `now`, pushing back the reload.
+    pub fn add(&mut self, dir: PathBuf, now: Instant) {
+        self.dirs.insert(dir);
+        self.last_change = Some(now);
+    }
+
+    /// How long to wait for more changes, or `None` if nothing is pending.
+    pub fn timeout(&self, now: Instant) -> Option<Duration> {
+        let last_change = self.last_change?;
+        Some(DEBOUNCE.saturating_sub(now.saturating_duration_since(last_change)))
+    }
+
+    /// The changed directories, once `DEBOUNCE` has passed without changes.
+    pub fn take_ready(&mut self, now: Instant) -> Option<BTreeSet<PathBuf>> {
+        if self.timeout(now)? > Duration::ZERO {
+            return None;
+        }
+        self.last_change = None;
+        Some(std::mem::take(&mut self.dirs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_for_changes_to_settle() {
+        let root = Path::new("/packs");
+        let dir = |path: &str| pack_dir(root, Path::new(path)).unwrap();
+        assert_eq!(dir("/packs/mine/a.png"), root.join("mine"));
+        assert_eq!(dir("/packs/new"), root.join("new"));
+        assert_eq!(pack_dir(root, root), None);
+        assert_eq!(pack_dir(root, Path::new("/packs/.new.partial/a.png")), None);
+        assert_eq!(pack_dir(root, Path::new("/elsewhere/a.png")), None);
+
+        let start = Instant::now();
+        let mut debounce = Debounce::default();
+        assert_eq!(debounce.timeout(start), None);
+        debounce.add(dir("/packs/mine/a.png"), start);
+        debounce.add(dir("/packs/other/pack.json"), start + DEBOUNCE / 2);
+        debounce.add(dir("/packs/mine/b.png"), start + DEBOUNCE / 2);
+        assert_eq!(debounce.take_ready(start + DEBOUNCE), None);
+        assert_eq!(debounce.timeout(start + DEBOUNCE), Some(DEBOUNCE / 2));
+
+        let ready = debounce.take_ready(start + DEBOUNCE * 2).unwrap();
+        assert_eq!(
+            ready.into_iter().collect::<Vec<_>>(),
+            vec![root.join("mine"), root.join("other")]
+        );
+        assert_eq!(debounce.take_ready(start + DEBOUNCE * 3), None);
+    }
+}
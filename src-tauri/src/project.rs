@@ -0,0 +1,192 @@
+mod migrations;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{BlockPackRef, Document, Grid, GridError};
+
+/// Extension used for saved projects, without the leading dot.
+pub const FILE_EXTENSION: &str = "binblock";
+
+/// Version written into every saved file. Bumping this requires adding a
+/// migration from the previous version in `project/migrations.rs`.
+pub const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProjectError {
+    #[error("failed to access project file: {0}")]
+    Io(#[from] io::Error),
+    #[error("project file is not valid: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("project file contains an invalid grid: {0}")]
+    Grid(#[from] GridError),
+    #[error("project file has no format version")]
+    MissingVersion,
+    #[error("unsupported project format version {0}")]
+    UnsupportedVersion(u32),
+    #[error(
+        "project was saved by a newer version of binblock++ (format {found}, this version supports up to {supported})"
+    )]
+    NewerVersion { found: u32, supported: u32 },
+    #[error("failed to upgrade project from format {from}: {reason}")]
+    Migration { from: u32, reason: String },
+    #[error("project file is malformed: {0}")]
+    Malformed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Version of binblock++ that last wrote the file.
+    pub app_version: String,
+    /// Seconds since the Unix epoch.
+    pub created_at: u64,
+    pub modified_at: u64,
+}
+
+impl Metadata {
+    /// Marks the project as modified now by this version of the app.
+    pub fn touch(&mut self) {
+        self.modified_at = now();
+        self.app_version = env!("CARGO_PKG_VERSION").to_owned();
+    }
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        let now = now();
+        Self {
+            title: None,
+            app_version: env!("CARGO_PKG_VERSION").to_owned(),
+            created_at: now,
+            modified_at: now,
+        }
+    }
+}
+
+/// A document plus the metadata stored alongside it in a `.binblock` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Project {
+    pub document: Document,
+    pub metadata: Metadata,
+}
+
+/// On-disk layout of a `.binblock` file at `FORMAT_VERSION`. Cells are
+/// stored row by row as indices into `palette`, with `null` for empty cells.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectFile {
+    format_version: u32,
+    cols: u32,
+    rows: u32,
+    pack: BlockPackRef,
+    palette: Vec<String>,
+    cells: Vec<Vec<Option<usize>>>,
+    metadata: Metadata,
+}
+
+impl Project {
+    pub fn to_json(&self) -> Result<Vec<u8>, ProjectError> {
+        let grid = &self.document.grid;
+        let palette: Vec<String> = grid.used_blocks().into_iter().map(str::to_owned).collect();
+        let indices: HashMap<&str, usize> = palette
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (id.as_str(), index))
+            .collect();
+
+        let cells = (0..grid.rows())
+            .map(|y| {
+                (0..grid.cols())
+                    .map(|x| Ok(grid.get(x, y)?.map(|id| indices[id])))
+                    .collect::<Result<Vec<_>, GridError>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let file = ProjectFile {
+            format_version: FORMAT_VERSION,
+            cols: grid.cols(),
+            rows: grid.rows(),
+            pack: self.document.pack.clone(),
+            palette,
+            cells,
+            metadata: self.metadata.clone(),
+        };
+        Ok(serde_json::to_vec_pretty(&file)?)
+    }
+
+    /// Parses a project file, upgrading it from older format versions first.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, ProjectError> {
+        let value = migrations::upgrade(serde_json::from_slice::<serde_json::Value>(bytes)?)?;
+        let file: ProjectFile = serde_json::from_value(value)?;
+
+        if file.cells.len() != file.rows as usize {
+            return Err(ProjectError::Malformed(format!(
+                "expected {} rows of cells, found {}",
+                file.rows,
+                file.cells.len()
+            )));
+        }
+
+        let mut grid = Grid::new(file.cols, file.rows)?;
+        for (y, row) in file.cells.iter().enumerate() {
+            if row.len() != file.cols as usize {
+                return Err(ProjectError::Malformed(format!(
+                    "row {y} has {} cells, expected {}",
+                    row.len(),
+                    file.cols
+                )));
+            }
+            for (x, cell) in row.iter().enumerate() {
+                let Some(index) = cell else { continue };
+                let id = file.palette.get(*index).ok_or_else(|| {
+                    ProjectError::Malformed(format!(
+                        "cell ({x}, {y}) refers to palette entry {index}, which does not exist"
+                    ))
+                })?;
+                grid.set(x as u32, y as u32, Some(id))?;
+            }
+        }
+
+        Ok(Self {
+            document: Document {
+                grid,
+                pack: file.pack,
+            },
+            metadata: file.metadata,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ProjectError> {
+        Self::from_json(&fs::read(path)?)
+    }
+
+    /// Writes the project to `path`, going through a temporary file so a
+    /// failed write never truncates an existing project.
+    pub fn save(&self, path: &Path) -> Result<(), ProjectError> {
+        write_atomic(path, &self.to_json()?)?;
+        Ok(())
+    }
+}
+
+/// Writes `bytes` to `path` through a temporary file next to it.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,110 @@
+//! Upgrades for `.binblock` files written by older releases.
+//!
+//! Each migration takes the raw JSON of one format version and returns the
+//! JSON of the next, so a file is brought up to date by running every
+//! migration from its version onward. Migrations operate on
+//! `serde_json::Value` rather than typed structs so old layouts never need to
+//! be kept around as Rust types.
+
+use serde_json::{json, Map, Value};
+
+use super::{ProjectError, FORMAT_VERSION};
+use crate::grid::{DEFAULT_BLOCK_ID, MAX_GRID_SIZE};
+
+type Migration = fn(Map<String, Value>) -> Result<Map<String, Value>, ProjectError>;
+
+/// `MIGRATIONS[n]` upgrades format `n + 1` to format `n + 2`.
+const MIGRATIONS: [Migration; FORMAT_VERSION as usize - 1] = [v1_to_v2];
+
+/// Brings a parsed project file up to `FORMAT_VERSION`.
+pub(super) fn upgrade(value: Value) -> Result<Value, ProjectError> {
+    let Value::Object(mut file) = value else {
+        return Err(ProjectError::Malformed("expected a JSON object".to_owned()));
+    };
+
+    let version = file
+        .get("formatVersion")
+        .ok_or(ProjectError::MissingVersion)?
+        .as_u64()
+        .and_then(|v| u32::try_from(v).ok())
+        .ok_or(ProjectError::MissingVersion)?;
+
+    if version > FORMAT_VERSION {
+        return Err(ProjectError::NewerVersion {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+    if version == 0 {
+        return Err(ProjectError::UnsupportedVersion(version));
+    }
+
+    for (from, migrate) in (version..).zip(&MIGRATIONS[version as usize - 1..]) {
+        file = migrate(file)?;
+        file.insert("formatVersion".to_owned(), json!(from + 1));
+    }
+
+    Ok(Value::Object(file))
+}
+
+/// Format 1 mirrored the frontend store: a `blockPack` id string and a sparse
+/// `"x,y" -> blockId` cell map. Format 2 adds the pack's default block and
+/// stores cells densely against a palette.
+fn v1_to_v2(mut file: Map<String, Value>) -> Result<Map<String, Value>, ProjectError> {
+    let fail = |reason: String| ProjectError::Migration { from: 1, reason };
+    let pack_id = match file.remove("blockPack") {
+        Some(Value::String(id)) => id,
+        _ => return Err(fail("missing blockPack".to_owned())),
+    };
+    let dimension = |name: &str| {
+        let size = file
+            .get(name)
+            .and_then(Value::as_u64)
+            .ok_or_else(|| fail(format!("missing {name}")))?;
+        // Checked before the dense grid below is allocated from it.
+        if size == 0 || size > u64::from(MAX_GRID_SIZE) {
+            return Err(ProjectError::Malformed(format!(
+                "{name} is {size}, must be between 1 and {MAX_GRID_SIZE}"
+            )));
+        }
+        Ok(size as usize)
+    };
+    let cols = dimension("cols")?;
+    let rows = dimension("rows")?;
+    let Some(Value::Object(sparse)) = file.remove("cells") else {
+        return Err(fail("missing cells".to_owned()));
+    };
+
+    let mut palette: Vec<String> = Vec::new();
+    let mut cells = vec![vec![Value::Null; cols]; rows];
+    for (key, id) in sparse {
+        let Value::String(id) = id else {
+            return Err(fail(format!("cell {key:?} is not a block id")));
+        };
+        let (x, y) = key
+            .split_once(',')
+            .and_then(|(x, y)| Some((x.parse::<usize>().ok()?, y.parse::<usize>().ok()?)))
+            .ok_or_else(|| fail(format!("invalid cell key {key:?}")))?;
+        let Some(cell) = cells.get_mut(y).and_then(|row| row.get_mut(x)) else {
+            return Err(fail(format!(
+                "cell {key:?} is outside the {cols}x{rows} grid"
+            )));
+        };
+        let index = match palette.iter().position(|existing| *existing == id) {
+            Some(index) => index,
+            None => {
+                palette.push(id);
+                palette.len() - 1
+            }
+        };
+        *cell = json!(index);
+    }
+
+    file.insert(
+        "pack".to_owned(),
+        json!({ "id": pack_id, "defaultBlock": DEFAULT_BLOCK_ID }),
+    );
+    file.insert("palette".to_owned(), json!(palette));
+    file.insert("cells".to_owned(), json!(cells));
+    Ok(file)
+}
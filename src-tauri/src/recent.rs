@@ -0,0 +1,102 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Most recent files kept in the Open Recent menu.
+pub const MAX_RECENT_FILES: usize = 10;
+
+/// Most-recently-used project files, newest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecentFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Reads the list from `path`, treating a missing or unreadable file as
+    /// empty, and drops entries whose files no longer exist.
+    pub fn load(path: &Path) -> Self {
+        let mut recent: Self = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        recent.prune();
+        recent
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Moves `path` to the front, removing any older entry for it.
+    pub fn add(&mut self, path: PathBuf) {
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        self.paths.retain(|existing| *existing != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(MAX_RECENT_FILES);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.paths.retain(|existing| existing != path);
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+    }
+
+    /// Drops entries whose files have been moved or deleted. Returns whether
+    /// anything was removed.
+    pub fn prune(&mut self) -> bool {
+        let before = self.paths.len();
+        self.paths.retain(|path| path.is_file());
+        self.paths.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_moves_existing_entries_to_the_front() {
+        let mut recent = RecentFiles::default();
+        recent.add("/a.binblock".into());
+        recent.add("/b.binblock".into());
+        recent.add("/a.binblock".into());
+        assert_eq!(
+            recent.paths(),
+            [PathBuf::from("/a.binblock"), PathBuf::from("/b.binblock")]
+        );
+    }
+
+    #[test]
+    fn add_caps_the_list() {
+        let mut recent = RecentFiles::default();
+        for i in 0..MAX_RECENT_FILES + 3 {
+            recent.add(format!("/{i}.binblock").into());
+        }
+        assert_eq!(recent.paths().len(), MAX_RECENT_FILES);
+        assert_eq!(
+            recent.paths()[0],
+            PathBuf::from(format!("/{}.binblock", MAX_RECENT_FILES + 2))
+        );
+    }
+
+    #[test]
+    fn prune_drops_missing_files() {
+        let mut recent = RecentFiles::default();
+        recent.add(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"));
+        recent.add("/definitely/not/here.binblock".into());
+        assert!(recent.prune());
+        assert_eq!(recent.paths().len(), 1);
+        assert!(!recent.prune());
+    }
+}
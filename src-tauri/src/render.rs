@@ -0,0 +1,420 @@
+//! Compositing documents into images. Cells are laid out left to right, top
+//! to bottom; optional grid lines sit in gutters between cells and an
+//! optional border surrounds the whole grid, so neither covers block pixels.
+
+pub mod animation;
+pub mod svg;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use image::imageops::{self, FilterType};
+use image::{Pixel, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::blocks::BlockSet;
+use crate::grid::Document;
+
+/// Cell size used by the editor's PNG export.
+pub const DEFAULT_CELL_SIZE: u32 = 100;
+
+/// Largest cell size accepted. A 64x64 grid is 16k pixels a side at this
+/// size before grid lines and border, and ~18.4k with the widest of both, so
+/// `MAX_OUTPUT_PIXELS` is what actually bounds big grids.
+pub const MAX_CELL_SIZE: u32 = 256;
+
+/// Most pixels rendered for one export, summed over every frame of an
+/// animation: 512 MiB of RGBA for a still image.
+pub const MAX_OUTPUT_PIXELS: u64 = 128 * 1024 * 1024;
+
+/// Widest grid line or border accepted.
+pub const MAX_STROKE_WIDTH: u32 = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("cell size must be between 1 and {MAX_CELL_SIZE}, got {0}")]
+    InvalidCellSize(u32),
+    #[error("line width must be at most {MAX_STROKE_WIDTH}, got {0}")]
+    InvalidStrokeWidth(u32),
+    #[error("block {0:?} is not in the loaded block set")]
+    MissingBlock(String),
+    #[error("the export would be {0} pixels, more than the {MAX_OUTPUT_PIXELS} pixel limit; use a smaller cell size")]
+    TooLarge(u64),
+}
+
+/// An RGBA colour written as `#rrggbb` or `#rrggbbaa`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Color(pub Rgba<u8>);
+
+#[derive(Debug, thiserror::Error)]
+#[error("invalid colour {0:?}, expected #rrggbb or #rrggbbaa")]
+pub struct ParseColorError(String);
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseColorError(s.to_owned());
+        let hex = s.strip_prefix('#').ok_or_else(err)?;
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return Err(err());
+        }
+
+        let mut channels = [u8::MAX; 4];
+        for (channel, i) in channels.iter_mut().zip((0..hex.len()).step_by(2)) {
+            *channel = u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err())?;
+        }
+        Ok(Self(Rgba(channels)))
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = ParseColorError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> Self {
+        color.to_string()
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [r, g, b, a] = self.0 .0;
+        write!(f, "#{r:02x}{g:02x}{b:02x}")?;
+        if a != u8::MAX {
+            write!(f, "{a:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A line drawn `width` pixels wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/bindings/")]
+pub struct Stroke {
+    #[ts(as = "String")]
+    pub color: Color,
+    pub width: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "../../src/bindings/", optional_fields)]
+pub struct RenderOptions {
+    /// Pixels per cell, before grid lines and border.
+    pub cell_size: u32,
+    /// Fill behind the blocks, visible through transparent pixels.
+    #[ts(as = "Option<String>")]
+    pub background: Option<Color>,
+    /// Lines between neighbouring cells.
+    pub grid_lines: Option<Stroke>,
+    /// Frame around the outside of the grid.
+    pub border: Option<Stroke>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: DEFAULT_CELL_SIZE,
+            background: None,
+            grid_lines: None,
+            border: None,
+        }
+    }
+}
+
+impl RenderOptions {
+    pub fn with_cell_size(cell_size: u32) -> Self {
+        Self {
+            cell_size,
+            ..Self::default()
+        }
+    }
+
+    fn validate(&self) -> Result<(), RenderError> {
+        if self.cell_size == 0 || self.cell_size > MAX_CELL_SIZE {
+            return Err(RenderError::InvalidCellSize(self.cell_size));
+        }
+        for stroke in [self.grid_lines, self.border].into_iter().flatten() {
+            if stroke.width > MAX_STROKE_WIDTH {
+                return Err(RenderError::InvalidStrokeWidth(stroke.width));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Pixel geometry of a rendered grid.
+struct Layout {
+    cell: u32,
+    gutter: u32,
+    border: u32,
+    cols: u32,
+    rows: u32,
+}
+
+impl Layout {
+    fn new(document: &Document, options: &RenderOptions) -> Self {
+        Self {
+            cell: options.cell_size,
+            gutter: options.grid_lines.map_or(0, |stroke| stroke.width),
+            border: options.border.map_or(0, |stroke| stroke.width),
+            cols: document.grid.cols(),
+            rows: document.grid.rows(),
+        }
+    }
+
+    fn span(&self, cells: u32) -> u32 {
+        2 * self.border + cells * self.cell + cells.saturating_sub(1) * self.gutter
+    }
+
+    fn width(&self) -> u32 {
+        self.span(self.cols)
+    }
+
+    fn height(&self) -> u32 {
+        self.span(self.rows)
+    }
+
+    /// Top-left pixel of the `index`th cell along either axis.
+    fn offset(&self, index: u32) -> u32 {
+        self.border + index * (self.cell + self.gutter)
+    }
+}
+
+/// Checks `options`, and that `frames` images of `document` rendered with
+/// them fit in `MAX_OUTPUT_PIXELS`, before anything is allocated.
+pub(crate) fn check_size(
+    document: &Document,
+    options: &RenderOptions,
+    frames: usize,
+) -> Result<(), RenderError> {
+    options.validate()?;
+    let layout = Layout::new(document, options);
+    let pixels = u64::from(layout.width())
+        .saturating_mul(u64::from(layout.height()))
+        .saturating_mul(frames as u64);
+    if pixels > MAX_OUTPUT_PIXELS {
+        return Err(RenderError::TooLarge(pixels));
+    }
+    Ok(())
+}
+
+/// Composites every cell of `document` according to `options`. Empty cells
+/// are drawn with the pack's default block, and animated blocks with their
+/// first frame.
+pub fn render(
+    document: &Document,
+    blocks: &BlockSet,
+    options: &RenderOptions,
+) -> Result<RgbaImage, RenderError> {
+    render_at(document, blocks, options, 0)
+}
+
+/// Like [`render`], but draws animated blocks as they appear `time_ms` into
+/// their loops.
+pub fn render_at(
+    document: &Document,
+    blocks: &BlockSet,
+    options: &RenderOptions,
+    time_ms: u64,
+) -> Result<RgbaImage, RenderError> {
+    check_size(document, options, 1)?;
+
+    let grid = &document.grid;
+    let layout = Layout::new(document, options);
+
+    let background = options.background.map_or(Rgba([0; 4]), |color| color.0);
+    let mut output = RgbaImage::from_pixel(layout.width(), layout.height(), background);
+    let mut scaled: HashMap<&str, RgbaImage> = HashMap::new();
+
+    for y in 0..grid.rows() {
+        for x in 0..grid.cols() {
+            let id = document
+                .block_at(x, y)
+                .expect("coordinates are within the grid");
+            if !scaled.contains_key(id) {
+                let block = blocks
+                    .get(id)
+                    .ok_or_else(|| RenderError::MissingBlock(id.to_owned()))?;
+                scaled.insert(id, scale_block(block.frame_at(time_ms), layout.cell));
+            }
+            imageops::overlay(
+                &mut output,
+                &scaled[id],
+                i64::from(layout.offset(x)),
+                i64::from(layout.offset(y)),
+            );
+        }
+    }
+
+    if let Some(stroke) = options.grid_lines.filter(|stroke| stroke.width > 0) {
+        let inner = layout.height() - 2 * layout.border;
+        for x in 1..layout.cols {
+            let left = layout.offset(x) - stroke.width;
+            fill_rect(
+                &mut output,
+                left,
+                layout.border,
+                stroke.width,
+                inner,
+                stroke.color,
+            );
+        }
+        let inner = layout.width() - 2 * layout.border;
+        for y in 1..layout.rows {
+            let top = layout.offset(y) - stroke.width;
+            fill_rect(
+                &mut output,
+                layout.border,
+                top,
+                inner,
+                stroke.width,
+                stroke.color,
+            );
+        }
+    }
+
+    if let Some(stroke) = options.border.filter(|stroke| stroke.width > 0) {
+        let (width, height, w) = (layout.width(), layout.height(), stroke.width);
+        fill_rect(&mut output, 0, 0, width, w, stroke.color);
+        fill_rect(&mut output, 0, height - w, width, w, stroke.color);
+        fill_rect(&mut output, 0, w, w, height - 2 * w, stroke.color);
+        fill_rect(&mut output, width - w, w, w, height - 2 * w, stroke.color);
+    }
+
+    Ok(output)
+}
+
+/// Blends `color` over a rectangle of `image`.
+fn fill_rect(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Color) {
+    for py in y..y + height {
+        for px in x..x + width {
+            image.get_pixel_mut(px, py).blend(&color.0);
+        }
+    }
+}
+
+/// Scales a block to a square cell. Upscaling uses nearest-neighbour so the
+/// pixel-art blocks stay crisp; downscaling filters to avoid aliasing.
+pub(crate) fn scale_block(image: &RgbaImage, cell_size: u32) -> RgbaImage {
+    if image.dimensions() == (cell_size, cell_size) {
+        return image.clone();
+    }
+    let filter = if image.width() < cell_size && image.height() < cell_size {
+        FilterType::Nearest
+    } else {
+        FilterType::Triangle
+    };
+    imageops::resize(image, cell_size, cell_size, filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::Block;
+    use crate::grid::Document;
+
+    fn solid(color: [u8; 4]) -> Block {
+        Block::still(RgbaImage::from_pixel(4, 4, Rgba(color)))
+    }
+
+    fn stroke(color: &str, width: u32) -> Option<Stroke> {
+        Some(Stroke {
+            color: color.parse().unwrap(),
+            width,
+        })
+    }
+
+    #[test]
+    fn draws_cells_and_fills_empty_ones_with_default_block() {
+        let mut blocks = BlockSet::default();
+        blocks.insert("12", solid([0, 0, 0, 255]));
+        blocks.insert("01", solid([255, 0, 0, 255]));
+
+        let mut document = Document::new(2, 1).unwrap();
+        document.grid.set(1, 0, Some("01")).unwrap();
+
+        let image = render(&document, &blocks, &RenderOptions::with_cell_size(8)).unwrap();
+        assert_eq!(image.dimensions(), (16, 8));
+        assert_eq!(image.get_pixel(3, 3), &Rgba([0, 0, 0, 255]));
+        assert_eq!(image.get_pixel(12, 3), &Rgba([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn grid_lines_and_border_sit_outside_cells() {
+        let mut blocks = BlockSet::default();
+        blocks.insert("12", solid([0, 0, 0, 0]));
+
+        let options = RenderOptions {
+            cell_size: 4,
+            background: Some("#0000ff".parse().unwrap()),
+            grid_lines: stroke("#00ff00", 1),
+            border: stroke("#ff0000", 2),
+        };
+        let image = render(&Document::new(2, 2).unwrap(), &blocks, &options).unwrap();
+
+        // 2 border + 4 cell + 1 line + 4 cell + 2 border
+        assert_eq!(image.dimensions(), (13, 13));
+        assert_eq!(image.get_pixel(0, 6), &Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(2, 2), &Rgba([0, 0, 255, 255]));
+        assert_eq!(image.get_pixel(6, 3), &Rgba([0, 255, 0, 255]));
+        assert_eq!(image.get_pixel(3, 6), &Rgba([0, 255, 0, 255]));
+        assert_eq!(image.get_pixel(7, 7), &Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn rejects_missing_blocks_and_bad_options() {
+        let document = Document::new(1, 1).unwrap();
+        let blocks = BlockSet::default();
+        assert!(matches!(
+            render(&document, &blocks, &RenderOptions::with_cell_size(8)),
+            Err(RenderError::MissingBlock(id)) if id == "12"
+        ));
+        assert!(matches!(
+            render(&document, &blocks, &RenderOptions::with_cell_size(0)),
+            Err(RenderError::InvalidCellSize(0))
+        ));
+        let options = RenderOptions {
+            border: stroke("#000000", 99),
+            ..RenderOptions::default()
+        };
+        assert!(matches!(
+            render(&document, &blocks, &options),
+            Err(RenderError::InvalidStrokeWidth(99))
+        ));
+
+        let options = RenderOptions {
+            cell_size: MAX_CELL_SIZE,
+            grid_lines: stroke("#000000", MAX_STROKE_WIDTH),
+            border: stroke("#000000", MAX_STROKE_WIDTH),
+            ..RenderOptions::default()
+        };
+        assert!(matches!(
+            render(&Document::new(64, 64).unwrap(), &blocks, &options),
+            Err(RenderError::TooLarge(pixels)) if pixels == 18_464 * 18_464
+        ));
+    }
+
+    #[test]
+    fn parses_and_formats_colors() {
+        let color: Color = "#FF8000".parse().unwrap();
+        assert_eq!(color.0, Rgba([255, 128, 0, 255]));
+        assert_eq!(color.to_string(), "#ff8000");
+        assert_eq!(
+            "#ff800080".parse::<Color>().unwrap().to_string(),
+            "#ff800080"
+        );
+        for bad in ["ff8000", "#ff80", "#gg8000", "#ff8000ff00"] {
+            assert!(bad.parse::<Color>().is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! Animated exports. Every animated block loops independently, so the output
+//! loops once all of them line up again: the least common multiple of their
+//! loop lengths. A new output frame starts whenever any block on the grid
+//! changes frame.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::iter;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::{check_size, render_at, RenderError, RenderOptions};
+use crate::blocks::BlockSet;
+use crate::grid::Document;
+
+/// Longest combined loop accepted, so blocks with awkward frame timings
+/// can't produce an effectively endless export.
+pub const MAX_LOOP_MS: u64 = 60_000;
+
+/// Most frames written to one export.
+pub const MAX_FRAMES: usize = 1_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnimationError {
+    #[error(transparent)]
+    Render(#[from] RenderError),
+    #[error("the animated blocks only line up every {length_ms}ms, longer than the {MAX_LOOP_MS}ms limit")]
+    LoopTooLong { length_ms: u64 },
+    #[error("the animation needs {0} frames, more than the {MAX_FRAMES} frame limit")]
+    TooManyFrames(usize),
+    #[error("failed to encode GIF: {0}")]
+    Gif(#[from] image::ImageError),
+    #[error("failed to encode APNG: {0}")]
+    Png(#[from] png::EncodingError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+/// When each output frame starts and how long it lasts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    starts: Vec<u64>,
+    length_ms: u64,
+}
+
+impl Timeline {
+    /// Works out the frames needed to play one full loop of every animated
+    /// block in `document`. Documents without animated blocks get a single
+    /// frame.
+    pub fn new(document: &Document, blocks: &BlockSet) -> Result<Self, AnimationError> {
+        let grid = &document.grid;
+        let mut animated = BTreeMap::new();
+        for y in 0..grid.rows() {
+            for x in 0..grid.cols() {
+                let id = document
+                    .block_at(x, y)
+                    .expect("coordinates are within the grid");
+                let block = blocks
+                    .get(id)
+                    .ok_or_else(|| RenderError::MissingBlock(id.to_owned()))?;
+                if block.is_animated() {
+                    animated.insert(id, block);
+                }
+            }
+        }
+
+        if animated.is_empty() {
+            return Ok(Self {
+                starts: vec![0],
+                length_ms: 0,
+            });
+        }
+        // Stop as soon as the loop passes the limit, before enough coprime
+        // durations can overflow it.
+        let mut length_ms = 1;
+        for block in animated.values() {
+            length_ms = match lcm(length_ms, block.duration_ms()) {
+                Some(length_ms) if length_ms <= MAX_LOOP_MS => length_ms,
+                next => {
+                    return Err(AnimationError::LoopTooLong {
+                        length_ms: next.unwrap_or(u64::MAX),
+                    })
+                }
+            };
+        }
+
+        let mut starts = BTreeSet::from([0]);
+        for block in animated.into_values() {
+            let mut start = 0;
+            while start < length_ms {
+                for frame in block.frames() {
+                    starts.insert(start);
+                    start += u64::from(frame.delay_ms);
+                }
+            }
+            if starts.len() > MAX_FRAMES {
+                return Err(AnimationError::TooManyFrames(starts.len()));
+            }
+        }
+
+        Ok(Self {
+            starts: starts.into_iter().collect(),
+            length_ms,
+        })
+    }
+
+    /// Length of one loop, or 0 if nothing on the grid is animated.
+    pub fn length_ms(&self) -> u64 {
+        self.length_ms
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Each frame's start time and delay, both in milliseconds.
+    pub fn frames(&self) -> impl Iterator<Item = (u64, u32)> + '_ {
+        self.starts.iter().enumerate().map(|(index, &start)| {
+            let end = self
+                .starts
+                .get(index + 1)
+                .copied()
+                .unwrap_or(self.length_ms);
+            (start, end.saturating_sub(start) as u32)
+        })
+    }
+}
+
+/// Renders one full loop of `document` and encodes it to `writer`. Frames are
+/// rendered and written one at a time, so only one is held in memory, but all
+/// of them together must fit in `MAX_OUTPUT_PIXELS`.
+pub fn encode<W: Write>(
+    document: &Document,
+    blocks: &BlockSet,
+    options: &RenderOptions,
+    format: AnimationFormat,
+    writer: W,
+) -> Result<Timeline, AnimationError> {
+    let timeline = Timeline::new(document, blocks)?;
+    check_size(document, options, timeline.len())?;
+    let mut frames = timeline.frames().map(|(start, delay_ms)| {
+        render_at(document, blocks, options, start).map(|image| (image, delay_ms))
+    });
+
+    match format {
+        AnimationFormat::Gif => {
+            let mut encoder = GifEncoder::new(writer);
+            encoder.set_repeat(Repeat::Infinite)?;
+            for frame in frames {
+                let (image, delay_ms) = frame?;
+                let delay = Delay::from_numer_denom_ms(delay_ms, 1);
+                encoder.encode_frame(Frame::from_parts(image, 0, 0, delay))?;
+            }
+        }
+        AnimationFormat::Apng => {
+            // The header needs the image size, so render the first frame
+            // before creating the encoder.
+            let (image, delay_ms) = frames
+                .next()
+                .expect("a timeline always has at least one frame")?;
+
+            let mut encoder = png::Encoder::new(writer, image.width(), image.height());
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(timeline.len() as u32, 0)?;
+            let mut png = encoder.write_header()?;
+
+            for frame in iter::once(Ok((image, delay_ms))).chain(frames) {
+                let (image, delay_ms) = frame?;
+                png.set_frame_delay(apng_delay(delay_ms), 1000)?;
+                png.write_image_data(image.as_raw())?;
+            }
+            png.finish()?;
+        }
+    }
+    Ok(timeline)
+}
+
+/// APNG delays are a u16 fraction of a second; loops are capped well below
+/// the limit, so this only clamps nonsense.
+fn apng_delay(delay_ms: u32) -> u16 {
+    delay_ms.min(u32::from(u16::MAX)) as u16
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// `None` if the result doesn't fit in a `u64`.
+fn lcm(a: u64, b: u64) -> Option<u64> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::codecs::png::PngDecoder;
+    use image::{AnimationDecoder, Rgba, RgbaImage};
+
+    use super::*;
+    use crate::blocks::{Block, BlockFrame};
+
+    fn animated(delays: &[u32]) -> Block {
+        let frames = delays
+            .iter()
+            .enumerate()
+            .map(|(index, &delay_ms)| BlockFrame {
+                image: RgbaImage::from_pixel(2, 2, Rgba([index as u8 * 50, 0, 0, 255])),
+                delay_ms,
+            })
+            .collect();
+        Block::animated(frames).unwrap()
+    }
+
+    fn fixture() -> (Document, BlockSet) {
+        let mut blocks = BlockSet::default();
+        blocks.insert("12", Block::still(RgbaImage::new(2, 2)));
+        blocks.insert("a", animated(&[100, 100]));
+        blocks.insert("b", animated(&[150, 150]));
+
+        let mut document = Document::new(3, 1).unwrap();
+        document.grid.set(0, 0, Some("a")).unwrap();
+        document.grid.set(1, 0, Some("b")).unwrap();
+        (document, blocks)
+    }
+
+    #[test]
+    fn timeline_covers_combined_loop() {
+        let (document, blocks) = fixture();
+        let timeline = Timeline::new(&document, &blocks).unwrap();
+        assert_eq!(timeline.length_ms(), 600);
+        assert_eq!(
+            timeline.frames().collect::<Vec<_>>(),
+            vec![
+                (0, 100),
+                (100, 50),
+                (150, 50),
+                (200, 100),
+                (300, 100),
+                (400, 50),
+                (450, 50),
+                (500, 100),
+            ]
+        );
+    }
+
+    #[test]
+    fn still_documents_have_one_frame() {
+        let (mut document, blocks) = fixture();
+        document.grid.clear();
+        let timeline = Timeline::new(&document, &blocks).unwrap();
+        assert_eq!(timeline.frames().collect::<Vec<_>>(), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn rejects_loops_that_never_line_up() {
+        let (mut document, mut blocks) = fixture();
+        blocks.insert("c", animated(&[7001, 100]));
+        document.grid.set(2, 0, Some("c")).unwrap();
+        assert!(matches!(
+            Timeline::new(&document, &blocks),
+            Err(AnimationError::LoopTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_many_coprime_loops_without_overflowing() {
+        const PRIMES: [u32; 16] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+        let mut blocks = BlockSet::default();
+        let mut document = Document::new(PRIMES.len() as u32, 1).unwrap();
+        for (x, prime) in PRIMES.into_iter().enumerate() {
+            let id = prime.to_string();
+            blocks.insert(&id, animated(&[prime - 1, 1]));
+            document.grid.set(x as u32, 0, Some(&id)).unwrap();
+        }
+        assert!(matches!(
+            Timeline::new(&document, &blocks),
+            Err(AnimationError::LoopTooLong { length_ms }) if length_ms > MAX_LOOP_MS
+        ));
+    }
+
+    #[test]
+    fn encodes_gif_and_apng() {
+        let (document, blocks) = fixture();
+        let options = RenderOptions::with_cell_size(2);
+
+        let mut gif = Vec::new();
+        encode(&document, &blocks, &options, AnimationFormat::Gif, &mut gif).unwrap();
+        let decoder = image::codecs::gif::GifDecoder::new(Cursor::new(&gif)).unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 8);
+        assert_eq!(frames[1].delay(), Delay::from_numer_denom_ms(50, 1));
+
+        let mut apng = Vec::new();
+        encode(
+            &document,
+            &blocks,
+            &options,
+            AnimationFormat::Apng,
+            &mut apng,
+        )
+        .unwrap();
+        let decoder = PngDecoder::new(Cursor::new(&apng)).unwrap();
+        let frames = decoder
+            .apng()
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 8);
+        assert_eq!(frames[0].buffer().dimensions(), (6, 2));
+        assert_eq!(frames[1].buffer()[(0, 0)], Rgba([50, 0, 0, 255]));
+    }
+
+    #[test]
+    fn counts_every_frame_against_the_pixel_budget() {
+        let (_, blocks) = fixture();
+        let mut document = Document::new(64, 64).unwrap();
+        document.grid.set(0, 0, Some("a")).unwrap();
+        document.grid.set(1, 0, Some("b")).unwrap();
+        // Each 4160x4160 frame fits the budget on its own, but all eight
+        // of them don't.
+        let options = RenderOptions::with_cell_size(65);
+        let mut output = Vec::new();
+        let result = encode(
+            &document,
+            &blocks,
+            &options,
+            AnimationFormat::Gif,
+            &mut output,
+        );
+        assert!(matches!(
+            result,
+            Err(AnimationError::Render(RenderError::TooLarge(pixels)))
+                if pixels == 8 * 4160 * 4160
+        ));
+        assert!(output.is_empty());
+    }
+}
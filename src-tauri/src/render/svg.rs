@@ -0,0 +1,221 @@
+//! Vector export. Each distinct block is embedded once in `<defs>` as a
+//! base64 PNG and every cell `<use>`s it, so the file stays small however
+//! many times a block repeats. One user unit is one cell, giving a viewBox of
+//! `cols x rows`; `cell_size` only sets the default display size.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Cursor;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use image::ImageFormat;
+
+use super::{Color, RenderError, RenderOptions, Stroke};
+use crate::blocks::BlockSet;
+use crate::grid::Document;
+
+/// Builds an SVG of `document`. Grid lines and the border are drawn over the
+/// cells rather than in gutters, so the viewBox stays exactly one unit per
+/// cell.
+pub fn render_svg(
+    document: &Document,
+    blocks: &BlockSet,
+    options: &RenderOptions,
+) -> Result<String, RenderError> {
+    options.validate()?;
+
+    let grid = &document.grid;
+    let (cols, rows) = (grid.cols(), grid.rows());
+
+    // Index each distinct block in the order it first appears.
+    let mut ids: Vec<&str> = Vec::new();
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    let mut cells = Vec::with_capacity((cols * rows) as usize);
+    for y in 0..rows {
+        for x in 0..cols {
+            let id = document
+                .block_at(x, y)
+                .expect("coordinates are within the grid");
+            let index = *indices.entry(id).or_insert_with(|| {
+                ids.push(id);
+                ids.len() - 1
+            });
+            cells.push((x, y, index));
+        }
+    }
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{}" height="{}" viewBox="0 0 {cols} {rows}" shape-rendering="crispEdges" style="image-rendering:pixelated">"#,
+        cols * options.cell_size,
+        rows * options.cell_size,
+    );
+
+    svg.push_str("  <defs>\n");
+    for (index, id) in ids.into_iter().enumerate() {
+        let block = blocks
+            .get(id)
+            .ok_or_else(|| RenderError::MissingBlock(id.to_owned()))?;
+        let mut png = Vec::new();
+        block
+            .image()
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .expect("encoding a PNG in memory cannot fail");
+        // SVG 1.1 tools only read `xlink:href`, and SVG 2 ones still do, so
+        // the image data isn't written twice. The short `<use>` links below
+        // carry both.
+        let _ = writeln!(
+            svg,
+            r#"    <image id="b{index}" data-block="{}" width="1" height="1" preserveAspectRatio="none" xlink:href="data:image/png;base64,{}"/>"#,
+            escape(id),
+            BASE64.encode(&png),
+        );
+    }
+    svg.push_str("  </defs>\n");
+
+    if let Some(color) = options.background {
+        let _ = writeln!(
+            svg,
+            r#"  <rect width="{cols}" height="{rows}"{}/>"#,
+            paint("fill", color)
+        );
+    }
+
+    for (x, y, index) in cells {
+        let _ = writeln!(
+            svg,
+            r##"  <use href="#b{index}" xlink:href="#b{index}" x="{x}" y="{y}"/>"##
+        );
+    }
+
+    let unit = |stroke: Stroke| f64::from(stroke.width) / f64::from(options.cell_size);
+
+    if let Some(stroke) = options.grid_lines.filter(|stroke| stroke.width > 0) {
+        let mut path = String::new();
+        for x in 1..cols {
+            let _ = write!(path, "M{x} 0V{rows}");
+        }
+        for y in 1..rows {
+            let _ = write!(path, "M0 {y}H{cols}");
+        }
+        if !path.is_empty() {
+            let _ = writeln!(
+                svg,
+                r#"  <path d="{path}" fill="none"{} stroke-width="{}"/>"#,
+                paint("stroke", stroke.color),
+                unit(stroke),
+            );
+        }
+    }
+
+    if let Some(stroke) = options.border.filter(|stroke| stroke.width > 0) {
+        let width = unit(stroke);
+        let _ = writeln!(
+            svg,
+            r#"  <rect x="{inset}" y="{inset}" width="{}" height="{}" fill="none"{} stroke-width="{width}"/>"#,
+            f64::from(cols) - width,
+            f64::from(rows) - width,
+            paint("stroke", stroke.color),
+            inset = width / 2.0,
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Writes `color` as an SVG 1.1 paint attribute plus a separate opacity, as
+/// `#rrggbbaa` isn't understood everywhere.
+fn paint(attribute: &str, color: Color) -> String {
+    let [r, g, b, a] = color.0 .0;
+    let mut paint = format!(r##" {attribute}="#{r:02x}{g:02x}{b:02x}""##);
+    if a != u8::MAX {
+        let _ = write!(
+            paint,
+            r#" {attribute}-opacity="{:.3}""#,
+            f64::from(a) / 255.0
+        );
+    }
+    paint
+}
+
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+    use crate::blocks::Block;
+
+    fn fixture() -> (Document, BlockSet) {
+        let mut blocks = BlockSet::default();
+        blocks.insert("12", Block::still(RgbaImage::new(2, 2)));
+        blocks.insert(
+            "a&b",
+            Block::still(RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255]))),
+        );
+
+        let mut document = Document::new(3, 2).unwrap();
+        document.grid.set(1, 0, Some("a&b")).unwrap();
+        document.grid.set(2, 1, Some("a&b")).unwrap();
+        (document, blocks)
+    }
+
+    #[test]
+    fn embeds_each_block_once() {
+        let (document, blocks) = fixture();
+        let svg = render_svg(&document, &blocks, &RenderOptions::with_cell_size(10)).unwrap();
+
+        assert!(svg.contains(r#"width="30" height="20" viewBox="0 0 3 2""#));
+        assert_eq!(svg.matches("<image ").count(), 2);
+        assert_eq!(svg.matches("<use ").count(), 6);
+        assert!(svg.contains(r#"<image id="b0" data-block="12""#));
+        assert!(svg.contains(r#"<image id="b1" data-block="a&amp;b""#));
+        assert!(svg.contains(r##"<use href="#b1" xlink:href="#b1" x="2" y="1"/>"##));
+        assert!(!svg.contains("<path"));
+    }
+
+    #[test]
+    fn draws_grid_lines_in_cell_units() {
+        let (document, blocks) = fixture();
+        let options = RenderOptions {
+            cell_size: 10,
+            background: Some("#ffffff".parse().unwrap()),
+            grid_lines: Some(Stroke {
+                color: "#00000080".parse().unwrap(),
+                width: 1,
+            }),
+            border: None,
+        };
+        let svg = render_svg(&document, &blocks, &options).unwrap();
+
+        assert!(svg.contains(r##"<rect width="3" height="2" fill="#ffffff"/>"##));
+        assert!(svg.contains(r#"d="M1 0V2M2 0V2M0 1H3""#));
+        assert!(svg.contains(r#"stroke-width="0.1""#));
+        assert!(svg.contains(r#"stroke-opacity="0.502""#));
+    }
+
+    #[test]
+    fn reports_missing_blocks() {
+        let (document, _) = fixture();
+        assert!(matches!(
+            render_svg(&document, &BlockSet::default(), &RenderOptions::default()),
+            Err(RenderError::MissingBlock(_))
+        ));
+    }
+}
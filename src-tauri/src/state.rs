@@ -0,0 +1,165 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::emoji::{EmojiError, EmojiProfiles};
+use crate::history::History;
+use crate::packs::PackLibrary;
+use crate::project::Project;
+use crate::recent::RecentFiles;
+
+/// The project currently open in the main window.
+#[derive(Debug, Default)]
+pub struct OpenDocument {
+    pub project: Project,
+    /// Where the project was last opened from or saved to.
+    pub path: Option<PathBuf>,
+    pub history: History,
+    /// Set when the project changes and cleared once it has been autosaved.
+    pub autosave_pending: bool,
+}
+
+impl OpenDocument {
+    /// Swaps in a different project, discarding the undo history.
+    pub fn replace(&mut self, project: Project, path: Option<PathBuf>) {
+        self.project = project;
+        self.path = path;
+        self.history.clear();
+        self.autosave_pending = true;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DocumentState(Mutex<OpenDocument>);
+
+impl DocumentState {
+    pub fn lock(&self) -> MutexGuard<'_, OpenDocument> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The recent files list and where it is persisted.
+#[derive(Debug)]
+pub struct RecentState {
+    file: PathBuf,
+    files: Mutex<RecentFiles>,
+}
+
+impl RecentState {
+    pub fn load(file: PathBuf) -> Self {
+        let files = Mutex::new(RecentFiles::load(&file));
+        Self { file, files }
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.lock().paths().to_vec()
+    }
+
+    /// Applies `change` to the list, persists it and returns the new paths.
+    pub fn update(&self, change: impl FnOnce(&mut RecentFiles)) -> io::Result<Vec<PathBuf>> {
+        let mut files = self.lock();
+        change(&mut files);
+        files.prune();
+        files.save(&self.file)?;
+        Ok(files.paths().to_vec())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RecentFiles> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Block packs available to the editor and the renderers, and the
+/// directories they were found in.
+#[derive(Debug)]
+pub struct BlockState {
+    user_dir: PathBuf,
+    library: Mutex<PackLibrary>,
+}
+
+impl BlockState {
+    /// Loads the packs bundled with the app and those in `user_dir`, logging
+    /// packs and blocks that were skipped so the editor still opens with
+    /// whatever did load. Bundled packs win when ids collide.
+    pub fn load(bundled_dir: PathBuf, user_dir: PathBuf) -> Self {
+        let library = PackLibrary::scan(&[bundled_dir, user_dir.clone()]);
+        for problem in library.problems() {
+            eprintln!("block packs: {problem}");
+        }
+        Self {
+            user_dir,
+            library: Mutex::new(library),
+        }
+    }
+
+    /// Where the user's own packs live.
+    pub fn user_dir(&self) -> &Path {
+        &self.user_dir
+    }
+
+    /// Loads the packs in `dirs` again and returns the ids of the packs that
+    /// changed, logging any problems found along the way.
+    pub fn reload<'a>(&self, dirs: impl IntoIterator<Item = &'a Path>) -> Vec<String> {
+        let mut library = self.lock();
+        let mut changed = Vec::new();
+        for dir in dirs {
+            for id in library.reload(dir) {
+                if !changed.contains(&id) {
+                    changed.push(id);
+                }
+            }
+            for problem in library.problems_in(dir) {
+                eprintln!("block packs: {problem}");
+            }
+        }
+        changed
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, PackLibrary> {
+        self.library.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Saved emoji profiles and where they are persisted.
+#[derive(Debug)]
+pub struct EmojiProfileState {
+    file: PathBuf,
+    profiles: Mutex<EmojiProfiles>,
+}
+
+impl EmojiProfileState {
+    /// Loads profiles from `file`. A corrupt file is logged and left alone
+    /// until the next change overwrites it.
+    pub fn load(file: PathBuf) -> Self {
+        let profiles = EmojiProfiles::load(&file).unwrap_or_else(|err| {
+            eprintln!("failed to load emoji profiles: {err}");
+            EmojiProfiles::default()
+        });
+        Self {
+            file,
+            profiles: Mutex::new(profiles),
+        }
+    }
+
+    pub fn get(&self) -> EmojiProfiles {
+        self.lock().clone()
+    }
+
+    /// Applies `change`, persists the result and returns it. Nothing is
+    /// saved if `change` fails.
+    pub fn update(
+        &self,
+        change: impl FnOnce(&mut EmojiProfiles) -> Result<(), EmojiError>,
+    ) -> Result<EmojiProfiles, EmojiError> {
+        let mut profiles = self.lock();
+        let mut updated = profiles.clone();
+        change(&mut updated)?;
+        updated.save(&self.file)?;
+        *profiles = updated.clone();
+        Ok(updated)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, EmojiProfiles> {
+        self.profiles.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
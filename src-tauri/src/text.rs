@@ -0,0 +1,243 @@
+//! Text exports for chat apps. Every cell becomes one token and every row one
+//! line; each target decides what a token looks like and how long a message
+//! may be. Long text is split into messages at row boundaries, and a row that
+//! doesn't fit in a message on its own either fails or wraps between tokens.
+
+pub mod matrix;
+pub mod slack;
+pub mod unicode;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::grid::Document;
+
+use self::matrix::MatrixOptions;
+use self::slack::SlackOptions;
+use self::unicode::UnicodeOptions;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TextError {
+    #[error(
+        "row {row} is too long for one message ({length}, limit {limit}); \
+         use a narrower grid or allow long rows to wrap"
+    )]
+    RowTooLong {
+        row: usize,
+        length: usize,
+        limit: usize,
+    },
+    #[error("emoji {emoji} is too long for one message (limit {limit})")]
+    EmojiTooLong { emoji: String, limit: usize },
+}
+
+/// What to do with a row that doesn't fit in one message on its own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum LongRows {
+    /// Refuse to split, reporting the first row that's too long.
+    #[default]
+    Error,
+    /// Break the row across several lines between emoji.
+    Wrap,
+}
+
+/// How a target measures message length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Length {
+    /// Unicode scalar values, as Discord and Slack count them.
+    Chars,
+    /// UTF-8 bytes, for targets whose limit is on the encoded message.
+    Bytes,
+}
+
+impl Length {
+    fn of(self, text: &str) -> usize {
+        match self {
+            Length::Chars => text.chars().count(),
+            Length::Bytes => text.len(),
+        }
+    }
+}
+
+/// How a target splits text into messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageLimits {
+    /// Longest message, measured in `length`.
+    pub max_length: usize,
+    pub length: Length,
+    /// Put between rows, and between the pieces of a wrapped row.
+    pub line_break: &'static str,
+    pub long_rows: LongRows,
+}
+
+/// A non-Discord text target and its options, as picked in the UI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum TextTarget {
+    Slack(SlackOptions),
+    Matrix(MatrixOptions),
+    Unicode(UnicodeOptions),
+}
+
+/// A text format a document can be posted in.
+pub trait TextExporter {
+    /// The text posted for one cell showing `block_id`.
+    fn token(&self, block_id: &str) -> String;
+
+    fn limits(&self) -> MessageLimits;
+
+    /// The document as messages ready to post, in order.
+    fn messages(&self, document: &Document) -> Result<Vec<String>, TextError> {
+        split_messages(&rows(document, |id| self.token(id)), &self.limits())
+    }
+}
+
+/// One token per cell, row by row. Empty cells use the pack's default block.
+pub fn rows(document: &Document, token: impl Fn(&str) -> String) -> Vec<Vec<String>> {
+    let grid = &document.grid;
+    (0..grid.rows())
+        .map(|y| {
+            (0..grid.cols())
+                .map(|x| {
+                    token(
+                        document
+                            .block_at(x, y)
+                            .expect("coordinates are within the grid"),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Joins rows of tokens into as few messages as `limits` allows, breaking
+/// only between rows unless a row has to wrap.
+pub fn split_messages(
+    rows: &[Vec<String>],
+    limits: &MessageLimits,
+) -> Result<Vec<String>, TextError> {
+    let limit = limits.max_length;
+    let mut lines = Vec::with_capacity(rows.len());
+    for (row, tokens) in rows.iter().enumerate() {
+        let line = tokens.concat();
+        let length = limits.length.of(&line);
+        if length <= limit {
+            lines.push(line);
+            continue;
+        }
+        match limits.long_rows {
+            LongRows::Error => {
+                return Err(TextError::RowTooLong {
+                    row: row + 1,
+                    length,
+                    limit,
+                })
+            }
+            LongRows::Wrap => lines.extend(wrap(tokens, limits)?),
+        }
+    }
+    Ok(pack_lines(lines, limits))
+}
+
+/// Breaks a row into lines of at most `limits.max_length` between tokens.
+fn wrap(tokens: &[String], limits: &MessageLimits) -> Result<Vec<String>, TextError> {
+    let limit = limits.max_length;
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut length = 0;
+    for token in tokens {
+        let token_length = limits.length.of(token);
+        if token_length > limit {
+            return Err(TextError::EmojiTooLong {
+                emoji: token.clone(),
+                limit,
+            });
+        }
+        if length + token_length > limit {
+            lines.push(std::mem::take(&mut current));
+            length = 0;
+        }
+        current.push_str(token);
+        length += token_length;
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    Ok(lines)
+}
+
+/// Joins lines into as few messages as fit in the limit. Every line must
+/// already fit on its own.
+fn pack_lines(lines: Vec<String>, limits: &MessageLimits) -> Vec<String> {
+    let limit = limits.max_length;
+    let break_length = limits.length.of(limits.line_break);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut length = 0;
+    for line in lines {
+        let line_length = limits.length.of(&line);
+        if !current.is_empty() && length + break_length + line_length > limit {
+            chunks.push(std::mem::take(&mut current));
+            length = 0;
+        }
+        if !current.is_empty() {
+            current.push_str(limits.line_break);
+            length += break_length;
+        }
+        current.push_str(&line);
+        length += line_length;
+    }
+    if !current.is_empty() || chunks.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_rows(rows: &[&[&str]]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|token| token.to_string()).collect())
+            .collect()
+    }
+
+    fn limits(max_length: usize, length: Length, line_break: &'static str) -> MessageLimits {
+        MessageLimits {
+            max_length,
+            length,
+            line_break,
+            long_rows: LongRows::Wrap,
+        }
+    }
+
+    #[test]
+    fn counts_line_breaks_against_the_limit() {
+        let rows = token_rows(&[&["aa"], &["bb"], &["cc"]]);
+        assert_eq!(
+            split_messages(&rows, &limits(10, Length::Chars, "<br>")).unwrap(),
+            vec!["aa<br>bb", "cc"]
+        );
+        assert_eq!(
+            split_messages(&rows, &limits(14, Length::Chars, "<br>")).unwrap(),
+            vec!["aa<br>bb<br>cc"]
+        );
+    }
+
+    #[test]
+    fn measures_bytes_or_chars() {
+        let rows = token_rows(&[&["é", "é"]]);
+        assert_eq!(
+            split_messages(&rows, &limits(2, Length::Chars, "\n")).unwrap(),
+            vec!["éé"]
+        );
+        assert_eq!(
+            split_messages(&rows, &limits(2, Length::Bytes, "\n")).unwrap(),
+            vec!["é", "é"]
+        );
+    }
+}
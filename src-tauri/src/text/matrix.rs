@@ -0,0 +1,137 @@
+//! Matrix (Element) emoji text. Custom emoji can be posted either as
+//! `:shortcode:` text, which Element replaces from the room's image packs,
+//! or as inline `<img>` HTML pointing at each block's uploaded `mxc://`
+//! image. Blocks without an uploaded image fall back to their shortcode.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::{Length, LongRows, MessageLimits, TextExporter};
+use crate::emoji::EmojiProfile;
+use crate::render::svg::escape;
+
+/// Matrix events are capped at 65,536 bytes including the JSON around the
+/// message and, for HTML, its plain-text fallback; half leaves room for both.
+pub const MATRIX_BYTE_LIMIT: usize = 32_768;
+
+/// Height in pixels of inline emoji, matching Element's own.
+const EMOTE_HEIGHT: u32 = 32;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase")]
+#[ts(export, export_to = "../../src/bindings/")]
+pub enum MatrixFormat {
+    /// `:shortcode:` text.
+    #[default]
+    Shortcodes,
+    /// `<img data-mx-emoticon>` tags, for a message's `formatted_body`.
+    Html,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "../../src/bindings/", optional_fields)]
+pub struct MatrixOptions {
+    pub format: MatrixFormat,
+    /// `mxc://` URI of each block's uploaded image, used by the HTML format.
+    pub sources: BTreeMap<String, String>,
+    /// Maximum UTF-8 bytes per message.
+    pub byte_limit: usize,
+    pub long_rows: LongRows,
+}
+
+impl Default for MatrixOptions {
+    fn default() -> Self {
+        Self {
+            format: MatrixFormat::default(),
+            sources: BTreeMap::new(),
+            byte_limit: MATRIX_BYTE_LIMIT,
+            long_rows: LongRows::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Matrix<'a> {
+    pub profile: Option<&'a EmojiProfile>,
+    pub options: &'a MatrixOptions,
+}
+
+impl TextExporter for Matrix<'_> {
+    fn token(&self, block_id: &str) -> String {
+        let name = self
+            .profile
+            .and_then(|profile| profile.emoji.get(block_id))
+            .map_or(block_id, |emoji| emoji.name());
+        let shortcode = format!(":{name}:");
+        let source = match self.options.format {
+            MatrixFormat::Shortcodes => None,
+            MatrixFormat::Html => self.options.sources.get(block_id),
+        };
+        match source {
+            Some(source) => {
+                let shortcode = escape(&shortcode);
+                format!(
+                    r#"<img data-mx-emoticon src="{}" alt="{shortcode}" title="{shortcode}" height="{EMOTE_HEIGHT}">"#,
+                    escape(source)
+                )
+            }
+            None => shortcode,
+        }
+    }
+
+    fn limits(&self) -> MessageLimits {
+        MessageLimits {
+            max_length: self.options.byte_limit,
+            length: Length::Bytes,
+            line_break: match self.options.format {
+                MatrixFormat::Shortcodes => "\n",
+                MatrixFormat::Html => "<br>",
+            },
+            long_rows: self.options.long_rows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Document;
+
+    #[test]
+    fn posts_images_or_shortcodes() {
+        let mut document = Document::new(2, 2).unwrap();
+        document.grid.set(0, 0, Some("03")).unwrap();
+        let mut options = MatrixOptions {
+            format: MatrixFormat::Html,
+            ..MatrixOptions::default()
+        };
+        options
+            .sources
+            .insert("03".into(), "mxc://example.org/abc".into());
+
+        let matrix = Matrix {
+            profile: None,
+            options: &options,
+        };
+        assert_eq!(
+            matrix.messages(&document).unwrap(),
+            vec![concat!(
+                r#"<img data-mx-emoticon src="mxc://example.org/abc" alt=":03:" title=":03:" height="32">"#,
+                ":12:<br>:12::12:"
+            )]
+        );
+
+        options.format = MatrixFormat::Shortcodes;
+        let matrix = Matrix {
+            profile: None,
+            options: &options,
+        };
+        assert_eq!(
+            matrix.messages(&document).unwrap(),
+            vec![":03::12:\n:12::12:"]
+        );
+    }
+}
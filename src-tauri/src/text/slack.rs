@@ -0,0 +1,81 @@
+//! Slack emoji text. Slack custom emoji have no ids and their names are
+//! always lowercase, so each cell is posted as `:name:` with the name taken
+//! from the emoji profile, or the block id.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::{Length, LongRows, MessageLimits, TextExporter};
+use crate::emoji::EmojiProfile;
+
+/// Slack truncates message text past 40,000 characters but asks apps to stay
+/// under 4,000, and clients show longer messages collapsed.
+pub const SLACK_CHAR_LIMIT: usize = 4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "../../src/bindings/", optional_fields)]
+pub struct SlackOptions {
+    /// Maximum characters per message.
+    pub char_limit: usize,
+    pub long_rows: LongRows,
+}
+
+impl Default for SlackOptions {
+    fn default() -> Self {
+        Self {
+            char_limit: SLACK_CHAR_LIMIT,
+            long_rows: LongRows::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Slack<'a> {
+    pub profile: Option<&'a EmojiProfile>,
+    pub options: SlackOptions,
+}
+
+impl TextExporter for Slack<'_> {
+    fn token(&self, block_id: &str) -> String {
+        let name = self
+            .profile
+            .and_then(|profile| profile.emoji.get(block_id))
+            .map_or(block_id, |emoji| emoji.name());
+        format!(":{}:", name.to_lowercase())
+    }
+
+    fn limits(&self) -> MessageLimits {
+        MessageLimits {
+            max_length: self.options.char_limit,
+            length: Length::Chars,
+            line_break: "\n",
+            long_rows: self.options.long_rows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Document;
+
+    #[test]
+    fn posts_lowercase_names_without_ids() {
+        let mut profile = EmojiProfile::default();
+        profile
+            .emoji
+            .insert("12".into(), "<:Blank:99>".parse().unwrap());
+        let mut document = Document::new(2, 2).unwrap();
+        document.grid.set(1, 1, Some("col_Blue")).unwrap();
+
+        let slack = Slack {
+            profile: Some(&profile),
+            options: SlackOptions::default(),
+        };
+        assert_eq!(
+            slack.messages(&document).unwrap(),
+            vec![":blank::blank:\n:blank::col_blue:"]
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Plain Unicode squares for places without custom emoji. Each block is
+//! posted as the coloured square emoji nearest its average colour, so the
+//! picture survives anywhere that renders standard emoji.
+
+use std::collections::HashMap;
+
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::{Length, LongRows, MessageLimits, TextExporter};
+use crate::blocks::BlockSet;
+
+/// The same limit as Discord, the most common place these are pasted.
+pub const UNICODE_CHAR_LIMIT: usize = 2000;
+
+/// The square emoji and their colours as most emoji fonts draw them.
+const SQUARES: [(&str, [u8; 3]); 9] = [
+    ("🟥", [221, 46, 68]),
+    ("🟧", [244, 144, 12]),
+    ("🟨", [253, 203, 88]),
+    ("🟩", [120, 177, 89]),
+    ("🟦", [85, 172, 238]),
+    ("🟪", [170, 142, 214]),
+    ("🟫", [193, 105, 79]),
+    ("⬛", [49, 55, 61]),
+    ("⬜", [230, 231, 232]),
+];
+
+/// Posted for blocks that are fully transparent or missing.
+const BLANK: &str = "⬜";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export, export_to = "../../src/bindings/", optional_fields)]
+pub struct UnicodeOptions {
+    /// Maximum characters per message.
+    pub char_limit: usize,
+    pub long_rows: LongRows,
+}
+
+impl Default for UnicodeOptions {
+    fn default() -> Self {
+        Self {
+            char_limit: UNICODE_CHAR_LIMIT,
+            long_rows: LongRows::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Unicode {
+    squares: HashMap<String, &'static str>,
+    options: UnicodeOptions,
+}
+
+impl Unicode {
+    /// Picks a square for every block in `blocks` up front.
+    pub fn new(blocks: &BlockSet, options: UnicodeOptions) -> Self {
+        let squares = blocks
+            .ids()
+            .filter_map(|id| {
+                let color = average_color(blocks.get(id)?.image())?;
+                Some((id.to_owned(), nearest_square(color)))
+            })
+            .collect();
+        Self { squares, options }
+    }
+}
+
+impl TextExporter for Unicode {
+    fn token(&self, block_id: &str) -> String {
+        self.squares
+            .get(block_id)
+            .copied()
+            .unwrap_or(BLANK)
+            .to_owned()
+    }
+
+    fn limits(&self) -> MessageLimits {
+        MessageLimits {
+            max_length: self.options.char_limit,
+            length: Length::Chars,
+            line_break: "\n",
+            long_rows: self.options.long_rows,
+        }
+    }
+}
+
+/// Mean colour weighted by alpha, or `None` if every pixel is transparent.
+fn average_color(image: &RgbaImage) -> Option<[u8; 3]> {
+    let mut sums = [0u64; 3];
+    let mut weight = 0u64;
+    for pixel in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        let a = u64::from(a);
+        for (sum, value) in sums.iter_mut().zip([r, g, b]) {
+            *sum += u64::from(value) * a;
+        }
+        weight += a;
+    }
+    (weight > 0).then(|| sums.map(|sum| (sum / weight) as u8))
+}
+
+fn nearest_square(color: [u8; 3]) -> &'static str {
+    let distance = |other: [u8; 3]| -> i32 {
+        color
+            .iter()
+            .zip(other)
+            .map(|(&a, b)| (i32::from(a) - i32::from(b)).pow(2))
+            .sum()
+    };
+    SQUARES
+        .iter()
+        .min_by_key(|(_, square)| distance(*square))
+        .map(|(emoji, _)| *emoji)
+        .expect("there is at least one square")
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+    use crate::blocks::Block;
+    use crate::grid::Document;
+
+    #[test]
+    fn maps_blocks_to_nearest_square() {
+        let mut blocks = BlockSet::default();
+        blocks.insert("12", Block::still(RgbaImage::new(2, 2)));
+        blocks.insert(
+            "red",
+            Block::still(RgbaImage::from_pixel(2, 2, Rgba([200, 30, 40, 255]))),
+        );
+        let mut navy = RgbaImage::from_pixel(2, 2, Rgba([20, 20, 120, 255]));
+        navy.put_pixel(0, 0, Rgba([255, 255, 255, 0]));
+        blocks.insert("navy", Block::still(navy));
+
+        let mut document = Document::new(3, 1).unwrap();
+        document.grid.set(0, 0, Some("red")).unwrap();
+        document.grid.set(1, 0, Some("navy")).unwrap();
+
+        let unicode = Unicode::new(&blocks, UnicodeOptions::default());
+        assert_eq!(unicode.messages(&document).unwrap(), vec!["🟥⬛⬜"]);
+        assert_eq!(unicode.token("missing"), BLANK);
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Instant;
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::Result;
+use crate::packs::watch::{self, BlocksChanged, Debounce};
+use crate::state::BlockState;
+
+/// Watches the user's packs directory and reloads the packs whose files
+/// change, emitting `blocks-changed` so the editor can pick up the new
+/// images. Bundled packs never change while the app runs, so they're left
+/// alone.
+pub fn init(app: &AppHandle) -> Result<()> {
+    let root = app.state::<BlockState>().user_dir().to_owned();
+    let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&root, RecursiveMode::Recursive)?;
+
+    let app = app.clone();
+    thread::spawn(move || {
+        // Dropping the watcher stops it, so it lives as long as the thread.
+        let _watcher = watcher;
+        let mut debounce = Debounce::default();
+        loop {
+            let received = match debounce.timeout(Instant::now()) {
+                Some(timeout) => rx.recv_timeout(timeout),
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(Ok(event)) if !matches!(event.kind, EventKind::Access(_)) => {
+                    for path in &event.paths {
+                        if let Some(dir) = watch::pack_dir(&root, path) {
+                            debounce.add(dir, Instant::now());
+                        }
+                    }
+                }
+                Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+                Ok(Err(err)) => eprintln!("block pack watcher: {err}"),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let Some(dirs) = debounce.take_ready(Instant::now()) else {
+                continue;
+            };
+            let packs = app
+                .state::<BlockState>()
+                .reload(dirs.iter().map(|dir| dir.as_path()));
+            if packs.is_empty() {
+                continue;
+            }
+            if let Err(err) = app.emit("blocks-changed", BlocksChanged { packs }) {
+                eprintln!("failed to emit blocks-changed: {err}");
+            }
+        }
+    });
+    Ok(())
+}
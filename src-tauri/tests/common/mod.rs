@@ -0,0 +1,60 @@
+//! Helpers shared by the integration tests.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use binblock_plusplus_lib::packs::PackLibrary;
+
+pub fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// The packs bundled with the app, laid out by the `bundle.resources` map in
+/// tauri.conf.json, as the app and the CLI find them next to the binary.
+/// They're copied once per test binary and must load without problems.
+pub fn bundled_packs() -> &'static PackLibrary {
+    static LIBRARY: OnceLock<PackLibrary> = OnceLock::new();
+    LIBRARY.get_or_init(|| {
+        let root = env::temp_dir().join(format!("binblock-bundle-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        stage_resources(&root);
+
+        let library = PackLibrary::scan(&[root.join("packs")]);
+        let problems: Vec<_> = library.problems().map(ToString::to_string).collect();
+        assert!(problems.is_empty(), "{problems:#?}");
+        fs::remove_dir_all(&root).unwrap();
+        library
+    })
+}
+
+/// Copies each `source: target` resource into `root`. Sources are files or
+/// `dir/*` and `dir/*.ext` globs; targets ending in `/` are directories.
+fn stage_resources(root: &Path) {
+    let config: serde_json::Value =
+        serde_json::from_slice(&fs::read(manifest_dir().join("tauri.conf.json")).unwrap())
+            .unwrap();
+    let resources = config["bundle"]["resources"].as_object().unwrap();
+    for (source, target) in resources {
+        let target = root.join(target.as_str().unwrap());
+        let sources: Vec<PathBuf> = match source.rsplit_once("/*") {
+            Some((dir, extension)) => fs::read_dir(manifest_dir().join(dir))
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.is_file())
+                .filter(|path| path.to_string_lossy().ends_with(extension))
+                .collect(),
+            None => vec![manifest_dir().join(source)],
+        };
+        for source in sources {
+            let destination = if target.to_string_lossy().ends_with('/') {
+                target.join(source.file_name().unwrap())
+            } else {
+                target.clone()
+            };
+            fs::create_dir_all(destination.parent().unwrap()).unwrap();
+            fs::copy(&source, &destination).unwrap();
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Converts a generated gradient with the stock blocks and compares the grid,
+//! written as one line of block ids per row, with text files in tests/golden.
+//! Run with `UPDATE_GOLDEN=1` to rewrite them after an intentional change to
+//! how pictures are converted.
+
+mod common;
+
+use std::env;
+use std::fs;
+
+use image::{Rgba, RgbaImage};
+
+use binblock_plusplus_lib::convert::dither::Dither;
+use binblock_plusplus_lib::convert::{self, ConvertOptions};
+use binblock_plusplus_lib::grid::Document;
+use binblock_plusplus_lib::packs::BUILTIN_PACK_ID;
+
+use common::{bundled_packs, manifest_dir};
+
+/// Deep blue on the left to sky blue on the right, with the top half
+/// brighter than the bottom: roughly the range the stock blocks cover.
+fn gradient() -> RgbaImage {
+    RgbaImage::from_fn(96, 64, |x, y| {
+        let across = x as f32 / 95.0;
+        let down = 0.8 + 0.2 * (1.0 - y as f32 / 63.0);
+        let channel = |from: f32, to: f32| ((from + (to - from) * across) * down).round() as u8;
+        Rgba([channel(0.0, 55.0), channel(10.0, 140.0), channel(140.0, 255.0), 255])
+    })
+}
+
+fn to_text(document: &Document) -> String {
+    let grid = &document.grid;
+    (0..grid.rows())
+        .map(|y| {
+            let row: Vec<_> = (0..grid.cols())
+                .map(|x| document.block_at(x, y).unwrap())
+                .collect();
+            row.join(" ") + "\n"
+        })
+        .collect()
+}
+
+fn assert_golden(name: &str, dither: Dither) {
+    let pack = bundled_packs().get(BUILTIN_PACK_ID).unwrap();
+    // The stock blocks, 00 to 12.
+    let stock = pack.groups.iter().find(|group| group.name == "blocks").unwrap();
+    let options = ConvertOptions {
+        cols: 24,
+        rows: 16,
+        match_texture: false,
+        dither,
+        blocks: Some(stock.blocks.clone()),
+    };
+    let document = convert::convert(&gradient(), &pack.blocks, &options, pack.pack_ref()).unwrap();
+    let actual = to_text(&document);
+
+    let golden = manifest_dir().join("tests/golden").join(format!("{name}.txt"));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&golden)
+        .unwrap_or_else(|err| panic!("failed to open {}: {err}", golden.display()));
+    assert_eq!(actual, expected, "{name}");
+}
+
+#[test]
+fn nearest_colour() {
+    assert_golden("convert-nearest", Dither::None);
+}
+
+#[test]
+fn floyd_steinberg() {
+    assert_golden("convert-floyd-steinberg", Dither::FloydSteinberg);
+}
+
+#[test]
+fn atkinson() {
+    assert_golden("convert-atkinson", Dither::Atkinson);
+}
+
+#[test]
+fn bayer() {
+    assert_golden("convert-bayer", Dither::Bayer);
+}
@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use binblock_plusplus_lib::project::{Project, ProjectError, FORMAT_VERSION};
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+fn assert_smiley(project: &Project) {
+    let grid = &project.document.grid;
+    assert_eq!((grid.cols(), grid.rows()), (3, 2));
+    assert_eq!(grid.get(0, 0).unwrap(), Some("05"));
+    assert_eq!(grid.get(1, 0).unwrap(), None);
+    assert_eq!(grid.get(2, 0).unwrap(), Some("col_blue_hi"));
+    assert_eq!(grid.get(1, 1).unwrap(), Some("05"));
+    assert_eq!(grid.get(2, 1).unwrap(), Some("12"));
+    assert_eq!(project.document.pack.id, "builtin");
+    assert_eq!(project.document.pack.default_block, "12");
+    assert_eq!(project.metadata.title.as_deref(), Some("Smiley"));
+    assert_eq!(project.metadata.created_at, 1767225600);
+}
+
+#[test]
+fn loads_current_format() {
+    assert_smiley(&Project::load(&fixture("v2.binblock")).unwrap());
+}
+
+#[test]
+fn migrates_v1() {
+    let project = Project::load(&fixture("v1.binblock")).unwrap();
+    assert_smiley(&project);
+    assert_eq!(project, Project::load(&fixture("v2.binblock")).unwrap());
+}
+
+#[test]
+fn saves_current_format() {
+    let project = Project::load(&fixture("v1.binblock")).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&project.to_json().unwrap()).unwrap();
+    assert_eq!(json["formatVersion"], FORMAT_VERSION);
+
+    let fixture: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(fixture("v2.binblock")).unwrap()).unwrap();
+    assert_eq!(json, fixture);
+}
+
+#[test]
+fn rejects_newer_versions() {
+    let err = Project::load(&fixture("future.binblock")).unwrap_err();
+    assert!(matches!(
+        err,
+        ProjectError::NewerVersion {
+            found: 999,
+            supported: FORMAT_VERSION
+        }
+    ));
+}
+
+#[test]
+fn rejects_missing_version() {
+    let err = Project::from_json(br#"{"cols": 1, "rows": 1}"#).unwrap_err();
+    assert!(matches!(err, ProjectError::MissingVersion));
+}
+
+#[test]
+fn rejects_malformed_cells() {
+    let json = br#"{
+        "formatVersion": 2, "cols": 2, "rows": 1,
+        "pack": { "id": "builtin", "defaultBlock": "12" },
+        "palette": ["05"], "cells": [[0, 1]],
+        "metadata": { "appVersion": "0.3.0", "createdAt": 0, "modifiedAt": 0 }
+    }"#;
+    assert!(matches!(
+        Project::from_json(json).unwrap_err(),
+        ProjectError::Malformed(_)
+    ));
+}
+
+#[test]
+fn rejects_v1_grids_too_large_to_migrate() {
+    let err = Project::load(&fixture("v1-huge.binblock")).unwrap_err();
+    assert!(matches!(err, ProjectError::Malformed(_)), "{err}");
+
+    let json = br#"{"formatVersion": 1, "blockPack": "builtin", "cols": 0, "rows": 2, "cells": {}}"#;
+    assert!(matches!(
+        Project::from_json(json).unwrap_err(),
+        ProjectError::Malformed(_)
+    ));
+}
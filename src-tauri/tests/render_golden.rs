@@ -0,0 +1,92 @@
+//! Renders a fixture project with the built-in blocks and compares the result
+//! with files in tests/golden: pixel for pixel for PNGs, byte for byte for
+//! SVGs. Run with `UPDATE_GOLDEN=1` to rewrite the golden images after an
+//! intentional rendering change.
+
+mod common;
+
+use std::env;
+use std::fs;
+
+use binblock_plusplus_lib::blocks::BlockSet;
+use binblock_plusplus_lib::project::Project;
+use binblock_plusplus_lib::render::{self, svg, RenderOptions, Stroke};
+
+use common::{bundled_packs, manifest_dir};
+
+fn fixture() -> (Project, &'static BlockSet) {
+    let project = Project::load(&manifest_dir().join("tests/fixtures/render.binblock")).unwrap();
+    let blocks = bundled_packs().blocks(&project.document.pack.id).unwrap();
+    (project, blocks)
+}
+
+fn assert_golden(name: &str, options: &RenderOptions) {
+    let (project, blocks) = fixture();
+    let actual = render::render(&project.document, blocks, options).unwrap();
+
+    let golden = manifest_dir().join("tests/golden").join(format!("{name}.png"));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&golden).unwrap();
+        return;
+    }
+
+    let expected = image::open(&golden)
+        .unwrap_or_else(|err| panic!("failed to open {}: {err}", golden.display()))
+        .into_rgba8();
+    assert_eq!(actual.dimensions(), expected.dimensions(), "{name}");
+    let mismatched = actual
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    assert_eq!(mismatched, 0, "{name}: {mismatched} pixels differ from golden");
+}
+
+#[test]
+fn plain_at_native_size() {
+    assert_golden("plain-14", &RenderOptions::with_cell_size(14));
+}
+
+#[test]
+fn upscaled() {
+    assert_golden("plain-32", &RenderOptions::with_cell_size(32));
+}
+
+#[test]
+fn grid_lines_border_and_background() {
+    let options = RenderOptions {
+        cell_size: 28,
+        background: Some("#ffffff".parse().unwrap()),
+        grid_lines: Some(Stroke {
+            color: "#00000040".parse().unwrap(),
+            width: 1,
+        }),
+        border: Some(Stroke {
+            color: "#202020".parse().unwrap(),
+            width: 3,
+        }),
+    };
+    assert_golden("decorated", &options);
+}
+
+#[test]
+fn svg_with_grid_lines() {
+    let (project, blocks) = fixture();
+    let options = RenderOptions {
+        cell_size: 32,
+        background: None,
+        grid_lines: Some(Stroke {
+            color: "#00000040".parse().unwrap(),
+            width: 2,
+        }),
+        border: None,
+    };
+    let actual = svg::render_svg(&project.document, blocks, &options).unwrap();
+
+    let golden = manifest_dir().join("tests/golden/grid-lines.svg");
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &actual).unwrap();
+        return;
+    }
+    assert_eq!(actual, fs::read_to_string(&golden).unwrap());
+}
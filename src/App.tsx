@@ -1,8 +1,18 @@
 import { useState, useRef, useEffect } from "react";
 import { CanvasController } from "./canvas";
+import { initBlocksListener } from "./blocks";
 import { BlockPalette } from "./components/BlockPalette";
 import { RightSidebar } from "./components/RightSidebar";
 import { initMenuListeners, cleanupMenuListeners, setCanvasRef } from "./menu";
+import {
+  exportAnimation,
+  exportPng,
+  exportSvg,
+  importImage,
+  initDocumentListener,
+  initRecovery,
+} from "./document";
+import { useGridStore } from "./store/gridStore";
 import "./App.css";
 
 export function App() {
@@ -21,6 +31,23 @@ export function App() {
     return () => cleanupMenuListeners();
   }, []);
 
+  // Follow backend document and block pack changes, and offer crash recovery
+  useEffect(() => {
+    const syncCanvas = () => controllerRef.current?.syncFromStore();
+    const unlistenDocument = initDocumentListener(syncCanvas);
+    const unlistenRecovery = initRecovery(syncCanvas);
+    const unlistenBlocks = initBlocksListener(({ packs }) => {
+      if (packs.includes(useGridStore.getState().pack.id)) {
+        controllerRef.current?.reloadBlocks().catch(console.error);
+      }
+    });
+    return () => {
+      unlistenDocument.then((unlisten) => unlisten());
+      unlistenRecovery.then((unlisten) => unlisten());
+      unlistenBlocks.then((unlisten) => unlisten());
+    };
+  }, []);
+
   useEffect(() => {
     const container = containerRef.current;
     if (!container) return;
@@ -60,6 +87,7 @@ export function App() {
         <BlockPalette
           selectedBlockId={selectedBlockId}
           onSelect={handleBlockSelect}
+          onGridLoaded={() => controllerRef.current?.syncFromStore()}
         />
       </aside>
       <div
@@ -72,7 +100,15 @@ export function App() {
           onResizeGrid={(cols, rows) =>
             controllerRef.current?.resizeGrid(cols, rows)
           }
-          onExportPng={() => controllerRef.current?.exportAsPng()}
+          onExportPng={() => exportPng().catch(console.error)}
+          onExportAnimation={() => exportAnimation().catch(console.error)}
+          onExportSvg={() => exportSvg().catch(console.error)}
+          onImportImage={() =>
+            importImage()
+              .then((loaded) => loaded && controllerRef.current?.syncFromStore())
+              .catch(console.error)
+          }
+          onGridLoaded={() => controllerRef.current?.syncFromStore()}
         />
       </aside>
     </main>
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A named run of blocks shown together in the palette.
+ */
+export type BlockGroup = { name: string, blocks: Array<string>, };
@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What people call a block besides its id, which is only a file name.
+ */
+export type BlockInfo = { 
+/**
+ * Shown in place of the id.
+ */
+name: string | null, tags: Array<string>, 
+/**
+ * Other names the block can be searched by.
+ */
+aliases: Array<string>, };
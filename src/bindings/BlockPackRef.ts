@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Where a document's blocks come from.
+ */
+export type BlockPackRef = { id: string, defaultBlock: string, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Payload of the `blocks-changed` event.
+ */
+export type BlocksChanged = { 
+/**
+ * Ids of the packs that were reloaded, added or removed.
+ */
+packs: Array<string>, };
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CatalogBlock = { id: string, 
+/**
+ * The image file as a `data:` URL.
+ */
+url: string, animated: boolean, 
+/**
+ * Shown in place of the id.
+ */
+name: string | null, tags: Array<string>, 
+/**
+ * Other names the block can be searched by.
+ */
+aliases: Array<string>, };
@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BlockGroup } from "./BlockGroup";
+import type { CatalogBlock } from "./CatalogBlock";
+
+export type CatalogPack = { id: string, name: string, defaultBlock: string, groups: Array<BlockGroup>, blocks: Array<CatalogBlock>, };
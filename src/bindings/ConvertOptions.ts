@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Dither } from "./Dither";
+
+export type ConvertOptions = { cols: number, rows: number, 
+/**
+ * Compare each quadrant of a cell as well as its average colour.
+ */
+matchTexture: boolean, dither: Dither, 
+/**
+ * Only pick from these blocks; every loaded block when missing.
+ */
+blocks?: Array<string>, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Emoji to use for each block when posting to one server.
+ */
+export type EmojiProfile = { emoji: { [key in string]?: string }, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { EmojiProfile } from "./EmojiProfile";
+
+/**
+ * Every saved profile and the one picked for export.
+ */
+export type EmojiProfiles = { active?: string, profiles: { [key in string]?: EmojiProfile }, };
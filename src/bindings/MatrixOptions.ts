@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LongRows } from "./LongRows";
+import type { MatrixFormat } from "./MatrixFormat";
+
+export type MatrixOptions = { format: MatrixFormat, 
+/**
+ * `mxc://` URI of each block's uploaded image, used by the HTML format.
+ */
+sources: { [key in string]?: string }, 
+/**
+ * Maximum UTF-8 bytes per message.
+ */
+byteLimit: number, longRows: LongRows, };
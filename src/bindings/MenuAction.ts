@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type MenuAction = { "type": "new" } | { "type": "open" } | { "type": "openRecent", index: number, } | { "type": "clearRecent" } | { "type": "importImage" } | { "type": "save" } | { "type": "saveAs" } | { "type": "exportPng" } | { "type": "exportAnimation" } | { "type": "exportSvg" } | { "type": "exportDiscord" } | { "type": "closeWindow" } | { "type": "quit" } | { "type": "undo" } | { "type": "redo" } | { "type": "clear" } | { "type": "resetView" };
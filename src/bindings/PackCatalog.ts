@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CatalogPack } from "./CatalogPack";
+
+/**
+ * The loaded packs as the frontend sees them.
+ */
+export type PackCatalog = { packs: Array<CatalogPack>, 
+/**
+ * Packs and blocks that were skipped, as messages.
+ */
+problems: Array<string>, };
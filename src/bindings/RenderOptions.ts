@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Stroke } from "./Stroke";
+
+export type RenderOptions = { 
+/**
+ * Pixels per cell, before grid lines and border.
+ */
+cellSize: number, 
+/**
+ * Fill behind the blocks, visible through transparent pixels.
+ */
+background?: string, 
+/**
+ * Lines between neighbouring cells.
+ */
+gridLines?: Stroke, 
+/**
+ * Frame around the outside of the grid.
+ */
+border?: Stroke, };
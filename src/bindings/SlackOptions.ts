@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { LongRows } from "./LongRows";
+
+export type SlackOptions = { 
+/**
+ * Maximum characters per message.
+ */
+charLimit: number, longRows: LongRows, };
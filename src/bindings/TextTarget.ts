@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { MatrixOptions } from "./MatrixOptions";
+import type { SlackOptions } from "./SlackOptions";
+import type { UnicodeOptions } from "./UnicodeOptions";
+
+/**
+ * A non-Discord text target and its options, as picked in the UI.
+ */
+export type TextTarget = { "kind": "slack" } & SlackOptions | { "kind": "matrix" } & MatrixOptions | { "kind": "unicode" } & UnicodeOptions;
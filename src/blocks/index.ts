@@ -1,19 +1,151 @@
-const blockModules = import.meta.glob<{ default: string }>("./*.png", {
-  eager: true,
-});
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import createDebug from "debug";
+import type { BlockMatch } from "../bindings/BlockMatch";
+import type { BlocksChanged } from "../bindings/BlocksChanged";
+import type { CatalogBlock } from "../bindings/CatalogBlock";
+import type { CatalogPack } from "../bindings/CatalogPack";
+import type { PackCatalog } from "../bindings/PackCatalog";
+
+const debug = createDebug("binblock:blocks");
+
+// Blocks come from the block packs the backend loads at runtime, not from
+// this folder: its images are bundled as the built-in pack (see pack.json).
+// Each document draws with one pack, `pack` in the grid store. Packs in the
+// user's packs directory are reloaded when their files change.
+// Animated blocks show their first frame in the editor and play in full in
+// animated exports.
+
+/** The pack new documents draw with, `BUILTIN_PACK_ID` in the backend. */
+export const BUILTIN_PACK_ID = "builtin";
+
+/** The pack imported blocks go into, `USER_PACK_ID` in the backend. */
+export const USER_PACK_ID = "custom";
 
 export const DEFAULT_BLOCK_ID = "12";
 
-export const blocks = Object.entries(blockModules)
-  .map(([path, module]) => {
-    const id = path.match(/\.\/(.+)\.png$/)?.[1];
-    if (!id) return null;
-    return { id, url: module.default };
-  })
-  .filter((b): b is { id: string; url: string } => b !== null)
-  .sort((a, b) => a.id.localeCompare(b.id, undefined, { numeric: true }));
+export type Block = CatalogBlock;
+
+let catalog: Promise<PackCatalog> | null = null;
+
+/**
+ * Every block pack the backend loaded and the problems it hit loading them.
+ * Fetched once and shared.
+ */
+export function loadBlockCatalog(): Promise<PackCatalog> {
+  catalog ??= invoke<PackCatalog>("block_catalog").then((loaded) => {
+    debug(
+      "loaded %d packs, %d problems",
+      loaded.packs.length,
+      loaded.problems.length
+    );
+    return loaded;
+  });
+  return catalog;
+}
+
+/**
+ * Follow the backend reloading packs whose files changed on disk. The shared
+ * catalog is dropped first, so `onChanged` sees the new blocks when it loads
+ * them again.
+ */
+export function initBlocksListener(
+  onChanged: (changed: BlocksChanged) => void
+): Promise<UnlistenFn> {
+  return listen<BlocksChanged>("blocks-changed", (event) => {
+    debug("packs changed: %o", event.payload.packs);
+    catalog = null;
+    onChanged(event.payload);
+  });
+}
+
+/**
+ * Block pack `packId`. Empty if it failed to load or isn't installed, such
+ * as a document's pack that was since removed.
+ */
+export async function loadBlockPack(packId: string): Promise<CatalogPack> {
+  const { packs } = await loadBlockCatalog();
+  return (
+    packs.find((pack) => pack.id === packId) ?? {
+      id: packId,
+      name: "",
+      defaultBlock: DEFAULT_BLOCK_ID,
+      groups: [],
+      blocks: [],
+    }
+  );
+}
+
+/**
+ * Blocks in pack `packId` whose id, name, tags or aliases match `query`,
+ * best first.
+ */
+export function searchBlocks(
+  query: string,
+  packId: string
+): Promise<BlockMatch[]> {
+  return invoke<BlockMatch[]>("search_blocks", { query, pack: packId });
+}
+
+/**
+ * Ask for pictures and add them as blocks to the user's custom pack. Returns
+ * their new ids, or an empty list if the user cancelled.
+ */
+export async function importBlocks(): Promise<string[]> {
+  const paths = await open({
+    multiple: true,
+    filters: [
+      { name: "Image", extensions: ["png", "jpg", "jpeg", "gif", "webp"] },
+    ],
+  });
+  if (!paths?.length) {
+    debug("importBlocks: user cancelled open dialog");
+    return [];
+  }
+  const ids = await invoke<string[]>("import_block", { paths });
+  // Don't wait for `blocks-changed` to drop the catalog, so the new blocks
+  // are there for whatever loads it next.
+  catalog = null;
+  debug("importBlocks: added %o to %s", ids, USER_PACK_ID);
+  return ids;
+}
+
+const PACK_FILTERS = [{ name: "Block pack", extensions: ["binpack"] }];
+
+/**
+ * Ask for a `.binpack` and install it with the user's own packs. Returns the
+ * new pack's id, or null if the user cancelled. The backend reports the new
+ * pack with `blocks-changed`.
+ */
+export async function importBlockPack(): Promise<string | null> {
+  const path = await open({ multiple: false, filters: PACK_FILTERS });
+  if (!path) {
+    debug("importBlockPack: user cancelled open dialog");
+    return null;
+  }
+  const id = await invoke<string>("import_block_pack", { path });
+  debug("importBlockPack: installed %s from %s", id, path);
+  return id;
+}
 
-export type Block = (typeof blocks)[number];
+/**
+ * Ask where to save pack `packId` as a `.binpack`, along with the active
+ * emoji profile's emoji for it. Returns false if the user cancelled.
+ */
+export async function exportBlockPack(packId: string): Promise<boolean> {
+  const path = await save({
+    defaultPath: `${packId}.binpack`,
+    filters: PACK_FILTERS,
+  });
+  if (!path) {
+    debug("exportBlockPack: user cancelled save dialog");
+    return false;
+  }
+  await invoke("export_block_pack", { packId, path });
+  debug("exportBlockPack: saved %s to %s", packId, path);
+  return true;
+}
 
 export type GridState = {
   cols: number;
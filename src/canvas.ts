@@ -7,11 +7,11 @@ import {
   Texture,
 } from "pixi.js";
 import createDebug from "debug";
-import { save } from "@tauri-apps/plugin-dialog";
-import { writeFile } from "@tauri-apps/plugin-fs";
-import { blocks, DEFAULT_BLOCK_ID, type GridState } from "./blocks";
+import { invoke } from "@tauri-apps/api/core";
+import { loadBlockPack, type GridState } from "./blocks";
 import { useGridStore } from "./store/gridStore";
-import { useAppStore } from "./store/appStore";
+import { useAppStore, TOOL_LABELS } from "./store/appStore";
+import type { GridSnapshot } from "./document";
 const cursorModules = import.meta.glob<{ default: string }>(
   "./icons/cursors/*.png",
   { eager: true }
@@ -65,6 +65,10 @@ export class CanvasController {
   private resizeObserver: ResizeObserver | null = null;
 
   private blockTextures: Map<string, Texture> = new Map();
+  // Pack `blockTextures` came from, and the one being loaded to replace it.
+  private packId: string | null = null;
+  private loadingPackId: string | null = null;
+  private blockLoads = 0;
   private cursorTextures: Map<string, Texture> = new Map();
   private gridData: Map<string, string> = new Map();
   private cellSprites: Map<string, Sprite> = new Map();
@@ -125,7 +129,46 @@ export class CanvasController {
   }
 
   private async loadBlocks(): Promise<void> {
-    debug("loading %d block textures", blocks.length);
+    const packId = useGridStore.getState().pack.id;
+    this.blockTextures = await this.loadBlockTextures(packId);
+    this.packId = packId;
+    await this.loadCursorTextures();
+  }
+
+  /**
+   * Load the textures of the document's pack, after the document switches
+   * packs or the backend reports changed packs, then redraw the grid with
+   * them. A reload started later wins over one still loading.
+   */
+  async reloadBlocks(): Promise<void> {
+    const packId = useGridStore.getState().pack.id;
+    const load = ++this.blockLoads;
+    this.loadingPackId = packId;
+    const textures = await this.loadBlockTextures(packId);
+    if (load !== this.blockLoads) {
+      for (const texture of textures.values()) {
+        texture.destroy(true);
+      }
+      return;
+    }
+
+    const previous = this.blockTextures;
+    this.blockTextures = textures;
+    this.packId = packId;
+    this.loadingPackId = null;
+    this.syncFromStore();
+    this.updateCursor();
+    for (const texture of previous.values()) {
+      texture.destroy(true);
+    }
+  }
+
+  private async loadBlockTextures(
+    packId: string
+  ): Promise<Map<string, Texture>> {
+    const { blocks } = await loadBlockPack(packId);
+    debug("loading %d block textures from %s", blocks.length, packId);
+    const textures = new Map<string, Texture>();
 
     const loadPromises = blocks.map(async (block) => {
       const img = new Image();
@@ -138,13 +181,15 @@ export class CanvasController {
         img.src = block.url;
       });
 
-      const texture = Texture.from(img);
-      this.blockTextures.set(block.id, texture);
+      textures.set(block.id, Texture.from(img));
     });
 
     await Promise.all(loadPromises);
-    debug("loaded %d block textures", this.blockTextures.size);
+    debug("loaded %d block textures", textures.size);
+    return textures;
+  }
 
+  private async loadCursorTextures(): Promise<void> {
     const cursors = [
       { id: "grab", url: cursorGrab },
       { id: "point", url: cursorPoint },
@@ -351,7 +396,7 @@ export class CanvasController {
     }
 
     if (event.button === 2) {
-      useGridStore.getState().pushHistory();
+      useGridStore.getState().beginEdit("Erase");
       this.isErasing = true;
       this.eraseAtPosition(event.global.x, event.global.y);
       return;
@@ -365,7 +410,7 @@ export class CanvasController {
     } else if (this.selectedBlockId) {
       const gridPos = this.screenToGrid(event.global.x, event.global.y);
 
-      useGridStore.getState().pushHistory();
+      useGridStore.getState().beginEdit(TOOL_LABELS[tool]);
 
       if (tool === "pencil") {
         this.isPainting = true;
@@ -417,6 +462,7 @@ export class CanvasController {
     if (this.isDrawingShape && this.shapeStartCell && this.selectedBlockId) {
       this.finalizeShape();
     }
+    useGridStore.getState().commitEdit();
 
     this.isDragging = false;
     this.isPainting = false;
@@ -572,7 +618,7 @@ export class CanvasController {
     if (!gridPos) return;
 
     const key = `${gridPos.x},${gridPos.y}`;
-    const blockId = this.gridData.get(key) ?? DEFAULT_BLOCK_ID;
+    const blockId = this.gridData.get(key) ?? this.defaultBlockId();
 
     debug("picked block %s from cell %s", blockId, key);
 
@@ -768,8 +814,9 @@ export class CanvasController {
   }
 
   private floodFill(startX: number, startY: number, fillBlockId: string): void {
+    const defaultBlockId = this.defaultBlockId();
     const targetBlockId =
-      this.gridData.get(`${startX},${startY}`) ?? DEFAULT_BLOCK_ID;
+      this.gridData.get(`${startX},${startY}`) ?? defaultBlockId;
 
     if (targetBlockId === fillBlockId) return;
 
@@ -783,7 +830,7 @@ export class CanvasController {
       if (visited.has(key) || !this.isInGrid(x, y)) continue;
       visited.add(key);
 
-      const currentBlockId = this.gridData.get(key) ?? DEFAULT_BLOCK_ID;
+      const currentBlockId = this.gridData.get(key) ?? defaultBlockId;
       if (currentBlockId !== targetBlockId) continue;
 
       this.fillCell(x, y, fillBlockId);
@@ -835,13 +882,20 @@ export class CanvasController {
   }
 
   clearCell(x: number, y: number): void {
-    this.fillCell(x, y, DEFAULT_BLOCK_ID);
-    debug("cleared cell %d,%d to %s", x, y, DEFAULT_BLOCK_ID);
+    const blockId = this.defaultBlockId();
+    this.fillCell(x, y, blockId);
+    debug("cleared cell %d,%d to %s", x, y, blockId);
+  }
+
+  /** The block empty cells show: the document pack's default block. */
+  private defaultBlockId(): string {
+    return useGridStore.getState().pack.defaultBlock;
   }
 
   private fillEmptyCells(): void {
     const { cols, rows, offsetX, offsetY, cellSize } = this.currentGridInfo;
-    const texture = this.blockTextures.get(DEFAULT_BLOCK_ID);
+    const defaultBlockId = this.defaultBlockId();
+    const texture = this.blockTextures.get(defaultBlockId);
     if (!texture) return;
 
     for (let y = 0; y < rows; y++) {
@@ -857,7 +911,7 @@ export class CanvasController {
 
         this.blocksContainer.addChild(sprite);
         this.cellSprites.set(key, sprite);
-        this.gridData.set(key, DEFAULT_BLOCK_ID);
+        this.gridData.set(key, defaultBlockId);
       }
     }
 
@@ -881,9 +935,6 @@ export class CanvasController {
   drawGrid(cols: number, rows: number): void {
     debug("drawGrid: %dx%d", cols, rows);
 
-    const dimensionsChanged =
-      cols !== this.currentGridInfo.cols || rows !== this.currentGridInfo.rows;
-
     if (this.gridGraphics) {
       this.gridGraphics.destroy();
     }
@@ -911,8 +962,9 @@ export class CanvasController {
       offsetY,
     };
 
-    if (dimensionsChanged) {
-      useGridStore.getState().clearGrid(cols, rows);
+    const store = useGridStore.getState();
+    if (store.cols !== cols || store.rows !== rows) {
+      store.clearGrid(cols, rows);
     }
 
     this.updateGridStroke();
@@ -1084,93 +1136,32 @@ export class CanvasController {
     this.hasMovedView = false;
   }
 
-  resizeGrid(newCols: number, newRows: number): void {
+  async resizeGrid(newCols: number, newRows: number): Promise<void> {
     const oldCols = this.currentGridInfo.cols;
     const oldRows = this.currentGridInfo.rows;
 
     if (newCols === oldCols && newRows === oldRows) return;
 
-    useGridStore.getState().pushHistory();
     debug("resizeGrid: %dx%d -> %dx%d", oldCols, oldRows, newCols, newRows);
 
-    const savedCells = new Map(this.gridData);
-
-    const colsToRemove = Math.max(0, oldCols - newCols);
-    const rowsToRemove = Math.max(0, oldRows - newRows);
-
-    let offsetX = 0;
-    let offsetY = 0;
-
-    if (rowsToRemove > 0) {
-      let emptyTopRows = 0;
-      for (let y = 0; y < oldRows && emptyTopRows < rowsToRemove; y++) {
-        let rowEmpty = true;
-        for (let x = 0; x < oldCols; x++) {
-          const blockId = savedCells.get(`${x},${y}`);
-          if (blockId && blockId !== DEFAULT_BLOCK_ID) {
-            rowEmpty = false;
-            break;
-          }
-        }
-        if (rowEmpty) {
-          emptyTopRows++;
-        } else {
-          break;
-        }
-      }
-      offsetY = Math.min(emptyTopRows, rowsToRemove);
-    }
-
-    if (colsToRemove > 0) {
-      let emptyLeftCols = 0;
-      for (let x = 0; x < oldCols && emptyLeftCols < colsToRemove; x++) {
-        let colEmpty = true;
-        for (let y = 0; y < oldRows; y++) {
-          const blockId = savedCells.get(`${x},${y}`);
-          if (blockId && blockId !== DEFAULT_BLOCK_ID) {
-            colEmpty = false;
-            break;
-          }
-        }
-        if (colEmpty) {
-          emptyLeftCols++;
-        } else {
-          break;
-        }
-      }
-      offsetX = Math.min(emptyLeftCols, colsToRemove);
-    }
-
-    debug("resizeGrid: sliding by offset (%d, %d)", offsetX, offsetY);
-
-    for (const sprite of this.cellSprites.values()) {
-      sprite.destroy();
-    }
-    this.cellSprites.clear();
-    this.gridData.clear();
-
-    this.drawGrid(newCols, newRows);
-    this.setInitialView();
-    this.hasMovedView = false;
-
-    for (const [key, blockId] of savedCells) {
-      const [xStr, yStr] = key.split(",");
-      const oldX = parseInt(xStr, 10);
-      const oldY = parseInt(yStr, 10);
-
-      const newX = oldX - offsetX;
-      const newY = oldY - offsetY;
-
-      if (newX >= 0 && newX < newCols && newY >= 0 && newY < newRows) {
-        this.fillCell(newX, newY, blockId);
-      }
-    }
+    // The backend slides the artwork past blank rows/columns and records the
+    // resize in the undo history.
+    await useGridStore.getState().commitEdit();
+    const grid = await invoke<GridSnapshot>("resize_document", {
+      cols: newCols,
+      rows: newRows,
+    });
+    useGridStore
+      .getState()
+      .setGrid(grid.cols, grid.rows, grid.cells, grid.pack);
+    this.syncFromStore();
 
-    debug("resizeGrid complete, preserved %d cells", this.gridData.size);
+    debug("resizeGrid complete, preserved %d cells", Object.keys(grid.cells).length);
   }
 
   clearAllCells(): void {
     debug("clearAllCells");
+    useGridStore.getState().beginEdit("Clear Grid");
 
     for (const sprite of this.cellSprites.values()) {
       sprite.destroy();
@@ -1183,13 +1174,21 @@ export class CanvasController {
       .clearGrid(this.currentGridInfo.cols, this.currentGridInfo.rows);
 
     this.fillEmptyCells();
+    useGridStore.getState().commitEdit();
     debug("cleared all cells");
   }
 
   syncFromStore(): void {
     debug("syncFromStore");
 
-    const { cols, rows, cells } = useGridStore.getState();
+    const { cols, rows, cells, pack } = useGridStore.getState();
+    if (pack.id !== this.packId) {
+      // Drawn once the new pack's textures are in.
+      if (pack.id !== this.loadingPackId) {
+        this.reloadBlocks().catch(console.error);
+      }
+      return;
+    }
 
     const dimensionsChanged =
       cols !== this.currentGridInfo.cols || rows !== this.currentGridInfo.rows;
@@ -1230,79 +1229,6 @@ export class CanvasController {
     this.fillEmptyCells();
     debug("syncFromStore complete, %d cells", this.cellSprites.size);
   }
-
-  async exportAsPng(): Promise<void> {
-    const EXPORT_CELL_SIZE = 100;
-    const { cols, rows } = this.currentGridInfo;
-
-    debug(
-      "exportAsPng: %dx%d grid, %dpx per cell",
-      cols,
-      rows,
-      EXPORT_CELL_SIZE
-    );
-
-    const canvas = document.createElement("canvas");
-    canvas.width = cols * EXPORT_CELL_SIZE;
-    canvas.height = rows * EXPORT_CELL_SIZE;
-    const ctx = canvas.getContext("2d");
-    if (!ctx) {
-      throw new Error("Failed to create 2D context for export");
-    }
-
-    ctx.clearRect(0, 0, canvas.width, canvas.height);
-
-    for (let y = 0; y < rows; y++) {
-      for (let x = 0; x < cols; x++) {
-        const key = `${x},${y}`;
-        const blockId = this.gridData.get(key) ?? DEFAULT_BLOCK_ID;
-        const texture = this.blockTextures.get(blockId);
-
-        if (texture && texture.source?.resource) {
-          const resource = texture.source.resource;
-
-          if (
-            resource instanceof HTMLImageElement ||
-            resource instanceof ImageBitmap
-          ) {
-            ctx.drawImage(
-              resource,
-              x * EXPORT_CELL_SIZE,
-              y * EXPORT_CELL_SIZE,
-              EXPORT_CELL_SIZE,
-              EXPORT_CELL_SIZE
-            );
-          }
-        }
-      }
-    }
-
-    const dataUrl = canvas.toDataURL("image/png");
-    const base64Data = dataUrl.replace(/^data:image\/png;base64,/, "");
-    const binaryString = atob(base64Data);
-    const bytes = new Uint8Array(binaryString.length);
-    for (let i = 0; i < binaryString.length; i++) {
-      bytes[i] = binaryString.charCodeAt(i);
-    }
-
-    const filePath = await save({
-      defaultPath: `binblock-${cols}x${rows}.png`,
-      filters: [
-        {
-          name: "PNG Image",
-          extensions: ["png"],
-        },
-      ],
-    });
-
-    if (!filePath) {
-      debug("exportAsPng: user cancelled save dialog");
-      return;
-    }
-
-    await writeFile(filePath, bytes);
-    debug("exportAsPng: saved to %s", filePath);
-  }
 }
 
 if (import.meta.hot) {
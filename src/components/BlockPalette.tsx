@@ -1,15 +1,23 @@
-import { useMemo } from "react";
-import { blocks, DEFAULT_BLOCK_ID } from "../blocks";
+import { useEffect, useMemo, useState } from "react";
 import {
-  blockGroups,
-  expandBlockGroup,
-  getGroupedBlockIds,
-} from "../blocks/groups";
+  exportBlockPack,
+  importBlockPack,
+  importBlocks,
+  initBlocksListener,
+  loadBlockCatalog,
+  loadBlockPack,
+  searchBlocks,
+  USER_PACK_ID,
+} from "../blocks";
+import type { CatalogPack } from "../bindings/CatalogPack";
+import { setDocumentPack } from "../document";
+import { useGridStore } from "../store/gridStore";
 import { cn } from "../lib/utils";
 
 interface BlockPaletteProps {
   selectedBlockId: string | null;
   onSelect: (blockId: string) => void;
+  onGridLoaded: () => void;
 }
 
 type BlockGroup = {
@@ -18,23 +26,97 @@ type BlockGroup = {
   isUngrouped: boolean;
 };
 
-export function BlockPalette({ selectedBlockId, onSelect }: BlockPaletteProps) {
-  const blockMap = useMemo(() => new Map(blocks.map((b) => [b.id, b])), []);
+export function BlockPalette({
+  selectedBlockId,
+  onSelect,
+  onGridLoaded,
+}: BlockPaletteProps) {
+  const packId = useGridStore((state) => state.pack.id);
+  const [packs, setPacks] = useState<CatalogPack[]>([]);
+  const [pack, setPack] = useState<CatalogPack | null>(null);
+  const [problems, setProblems] = useState<string[]>([]);
+  const [packError, setPackError] = useState<string | null>(null);
+  const [query, setQuery] = useState("");
+  const [matches, setMatches] = useState<string[] | null>(null);
+
+  useEffect(() => {
+    const load = () => {
+      loadBlockPack(packId).then(setPack);
+      loadBlockCatalog().then((catalog) => {
+        setPacks(catalog.packs);
+        setProblems(catalog.problems);
+      });
+    };
+    load();
+    const unlisten = initBlocksListener(load);
+    return () => {
+      unlisten.then((unlisten) => unlisten());
+    };
+  }, [packId]);
+
+  useEffect(() => {
+    if (!pack || !query.trim()) {
+      setMatches(null);
+      return;
+    }
+    let stale = false;
+    searchBlocks(query, pack.id).then((found) => {
+      if (!stale) setMatches(found.map((match) => match.id));
+    });
+    return () => {
+      stale = true;
+    };
+  }, [pack, query]);
+
+  const runPackAction = async (action: () => Promise<unknown>) => {
+    try {
+      await action();
+      setPackError(null);
+    } catch (err) {
+      setPackError(String(err));
+    }
+  };
+
+  // Imported blocks can only be drawn with once the grid uses the custom
+  // pack, so offer to switch to it.
+  const importBlocksAndSelect = async () => {
+    const ids = await importBlocks();
+    if (ids.length === 0) return;
+    if (packId !== USER_PACK_ID) {
+      const blocks = ids.length === 1 ? "the new block" : "the new blocks";
+      const question =
+        `Switch this grid to the custom block pack to draw with ${blocks}?`;
+      if (!window.confirm(question)) return;
+      await setDocumentPack(USER_PACK_ID);
+      onGridLoaded();
+    }
+    onSelect(ids[0]);
+  };
+
+  const blockMap = useMemo(
+    () => new Map(pack?.blocks.map((b) => [b.id, b])),
+    [pack]
+  );
 
   const groups = useMemo((): BlockGroup[] => {
+    if (!pack) return [];
+    if (matches) {
+      return [{ key: "_matches", blockIds: matches, isUngrouped: false }];
+    }
     const result: BlockGroup[] = [];
-    const groupedIds = getGroupedBlockIds(blockGroups);
+    const groupedIds = new Set(pack.groups.flatMap((group) => group.blocks));
 
-    for (const [key, specifiers] of Object.entries(blockGroups)) {
-      const blockIds = expandBlockGroup(specifiers).filter((id) =>
-        blockMap.has(id)
-      );
-      if (blockIds.length > 0) {
-        result.push({ key, blockIds, isUngrouped: false });
+    for (const group of pack.groups) {
+      if (group.blocks.length > 0) {
+        result.push({
+          key: group.name,
+          blockIds: group.blocks,
+          isUngrouped: false,
+        });
       }
     }
 
-    const ungroupedIds = blocks
+    const ungroupedIds = pack.blocks
       .map((b) => b.id)
       .filter((id) => !groupedIds.has(id));
 
@@ -47,11 +129,40 @@ export function BlockPalette({ selectedBlockId, onSelect }: BlockPaletteProps) {
     }
 
     return result;
-  }, [blockMap]);
+  }, [pack, matches]);
 
   return (
     <div className="h-full flex flex-col">
+      <div className="flex flex-col gap-2 p-4 pb-0">
+        <select
+          value={packId}
+          onChange={(e) => {
+            const id = e.target.value;
+            runPackAction(() => setDocumentPack(id).then(onGridLoaded));
+          }}
+          className="w-full px-2 py-1 text-xs bg-white border border-black/20 rounded focus:outline-none focus:border-black/40"
+        >
+          {packs.map((pack) => (
+            <option key={pack.id} value={pack.id}>
+              {pack.name || pack.id}
+            </option>
+          ))}
+          {!packs.some((pack) => pack.id === packId) && (
+            <option value={packId}>{packId} (not installed)</option>
+          )}
+        </select>
+        <input
+          type="search"
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          placeholder="Search blocks"
+          className="w-full px-2 py-1 text-xs bg-white border border-black/20 rounded focus:outline-none focus:border-black/40"
+        />
+      </div>
       <div className="flex-1 overflow-y-auto space-y-3 p-4">
+        {matches?.length === 0 && (
+          <div className="text-xs text-black/50">No blocks match.</div>
+        )}
         {groups.map((group) => (
           <div
             key={group.key}
@@ -77,11 +188,15 @@ export function BlockPalette({ selectedBlockId, onSelect }: BlockPaletteProps) {
                   >
                     <img
                       src={block.url}
-                      alt={`Block ${block.id}`}
-                      title={`:${block.id}:`}
+                      alt={`Block ${block.name ?? block.id}`}
+                      title={
+                        block.name
+                          ? `${block.name} (:${block.id}:)`
+                          : `:${block.id}:`
+                      }
                       className={cn(
                         "w-full h-full object-contain",
-                        block.id === DEFAULT_BLOCK_ID &&
+                        block.id === pack?.defaultBlock &&
                           "border-2 border-black"
                       )}
                       draggable={false}
@@ -93,6 +208,45 @@ export function BlockPalette({ selectedBlockId, onSelect }: BlockPaletteProps) {
           </div>
         ))}
       </div>
+      <div className="flex flex-col gap-2 p-4 pt-0">
+        <button
+          onClick={() => runPackAction(importBlocksAndSelect)}
+          className="w-full py-1.5 text-xs rounded transition-colors bg-black/10 text-black/70 hover:bg-black/20 active:bg-black/30"
+        >
+          Import Block…
+        </button>
+        <div className="flex gap-2">
+          <button
+            onClick={() => runPackAction(importBlockPack)}
+            className="flex-1 py-1.5 text-xs rounded transition-colors bg-black/10 text-black/70 hover:bg-black/20 active:bg-black/30"
+          >
+            Import Pack…
+          </button>
+          <button
+            onClick={() =>
+              pack && runPackAction(() => exportBlockPack(pack.id))
+            }
+            disabled={!pack?.blocks.length}
+            className="flex-1 py-1.5 text-xs rounded transition-colors bg-black/10 text-black/70 hover:bg-black/20 active:bg-black/30 disabled:opacity-50"
+          >
+            Export Pack…
+          </button>
+        </div>
+        {packError && <div className="text-xs text-red-600">{packError}</div>}
+      </div>
+      {problems.length > 0 && (
+        <details className="max-h-40 overflow-y-auto p-4 pt-0 text-xs text-red-700">
+          <summary className="cursor-pointer">
+            {problems.length} block pack{" "}
+            {problems.length === 1 ? "problem" : "problems"}
+          </summary>
+          <ul className="mt-1 space-y-1">
+            {problems.map((problem, index) => (
+              <li key={index}>{problem}</li>
+            ))}
+          </ul>
+        </details>
+      )}
     </div>
   );
 }
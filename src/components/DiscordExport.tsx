@@ -1,19 +1,93 @@
-import { useState } from "react";
-import {
-  useGridStore,
-  toDiscordText,
-  splitDiscordText,
-} from "../store/gridStore";
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useGridStore } from "../store/gridStore";
+import type { DiscordOptions } from "../bindings/DiscordOptions";
+import type { EmojiProfiles } from "../bindings/EmojiProfiles";
+import type { LongRows } from "../bindings/LongRows";
+import type { TextTarget } from "../bindings/TextTarget";
+import { EmojiProfileEditor } from "./EmojiProfileEditor";
+
+interface DiscordMessages {
+  messages: string[];
+  unmapped: string[];
+}
 
 type CharLimit = 2000 | 4000;
 
+type TextFormat = "discord" | "slack" | "matrix" | "unicode";
+
+const FORMATS: { id: TextFormat; label: string }[] = [
+  { id: "discord", label: "Discord" },
+  { id: "slack", label: "Slack" },
+  { id: "matrix", label: "Matrix" },
+  { id: "unicode", label: "Unicode squares" },
+];
+
+/**
+ * Options for a non-Discord target at its own message limit, matching the
+ * defaults in src-tauri/src/text.
+ */
+function textTarget(format: TextFormat, longRows: LongRows): TextTarget {
+  switch (format) {
+    case "slack":
+      return { kind: "slack", charLimit: 4000, longRows };
+    case "matrix":
+      return {
+        kind: "matrix",
+        format: "shortcodes",
+        sources: {},
+        byteLimit: 32768,
+        longRows,
+      };
+    default:
+      return { kind: "unicode", charLimit: 2000, longRows };
+  }
+}
+
 export function DiscordExport() {
+  const [format, setFormat] = useState<TextFormat>("discord");
   const [charLimit, setCharLimit] = useState<CharLimit>(2000);
+  const [wrapLongRows, setWrapLongRows] = useState(false);
+  const [chunks, setChunks] = useState<string[]>([]);
+  const [unmapped, setUnmapped] = useState<string[]>([]);
+  const [profiles, setProfiles] = useState<EmojiProfiles>({ profiles: {} });
+  const [error, setError] = useState<string | null>(null);
   const [copiedIndex, setCopiedIndex] = useState<number | null>(null);
-  const { cols, rows, cells } = useGridStore();
+  const revision = useGridStore((state) => state.revision);
 
-  const discordText = toDiscordText(cols, rows, cells);
-  const chunks = splitDiscordText(discordText, charLimit);
+  useEffect(() => {
+    invoke<EmojiProfiles>("emoji_profiles").then(setProfiles);
+  }, []);
+
+  useEffect(() => {
+    let cancelled = false;
+    const longRows: LongRows = wrapLongRows ? "wrap" : "error";
+    const options: DiscordOptions = { charLimit, longRows };
+    const request =
+      format === "discord"
+        ? invoke<DiscordMessages>("discord_messages", { options })
+        : invoke<string[]>("text_messages", {
+            target: textTarget(format, longRows),
+          }).then((messages) => ({ messages, unmapped: [] }));
+    request
+      .then(({ messages, unmapped }) => {
+        if (cancelled) return;
+        setChunks(messages);
+        setUnmapped(unmapped);
+        setError(null);
+      })
+      .catch((err) => {
+        if (cancelled) return;
+        setChunks([]);
+        setUnmapped([]);
+        setError(String(err));
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [revision, format, charLimit, wrapLongRows, profiles]);
+
+  const totalChars = chunks.reduce((total, chunk) => total + chunk.length, 0);
 
   const copyToClipboard = async (text: string, index: number) => {
     await navigator.clipboard.writeText(text);
@@ -22,30 +96,62 @@ export function DiscordExport() {
   };
 
   return (
-    <div className="flex flex-col h-full gap-3">
-      <h3 className="text-xs font-medium text-black/50">Message Limit</h3>
-      <div className="flex items-center gap-2">
-        <button
-          onClick={() => setCharLimit(2000)}
-          className={`px-2 py-1 text-xs rounded transition-colors ${
-            charLimit === 2000
-              ? "bg-black text-white"
-              : "bg-black/10 text-black/60 hover:bg-black/20"
-          }`}
-        >
-          2K
-        </button>
-        <button
-          onClick={() => setCharLimit(4000)}
-          className={`px-2 py-1 text-xs rounded transition-colors ${
-            charLimit === 4000
-              ? "bg-black text-white"
-              : "bg-black/10 text-black/60 hover:bg-black/20"
-          }`}
-        >
-          4K (Nitro)
-        </button>
-      </div>
+    <div className="flex flex-col flex-1 min-h-0 gap-3">
+      <h3 className="text-xs font-medium text-black/50">Chat Text</h3>
+      <select
+        value={format}
+        onChange={(e) => setFormat(e.target.value as TextFormat)}
+        className="px-1 py-1 text-xs bg-white border border-black/20 rounded"
+      >
+        {FORMATS.map(({ id, label }) => (
+          <option key={id} value={id}>
+            {label}
+          </option>
+        ))}
+      </select>
+      {format === "discord" && (
+        <div className="flex items-center gap-2">
+          <button
+            onClick={() => setCharLimit(2000)}
+            className={`px-2 py-1 text-xs rounded transition-colors ${
+              charLimit === 2000
+                ? "bg-black text-white"
+                : "bg-black/10 text-black/60 hover:bg-black/20"
+            }`}
+          >
+            2K
+          </button>
+          <button
+            onClick={() => setCharLimit(4000)}
+            className={`px-2 py-1 text-xs rounded transition-colors ${
+              charLimit === 4000
+                ? "bg-black text-white"
+                : "bg-black/10 text-black/60 hover:bg-black/20"
+            }`}
+          >
+            4K (Nitro)
+          </button>
+        </div>
+      )}
+      <label className="flex items-center gap-1.5 text-xs text-black/70">
+        <input
+          type="checkbox"
+          checked={wrapLongRows}
+          onChange={(e) => setWrapLongRows(e.target.checked)}
+        />
+        Wrap rows that don't fit in one message
+      </label>
+
+      {format !== "unicode" && (
+        <EmojiProfileEditor profiles={profiles} onChange={setProfiles} />
+      )}
+
+      {error && <div className="text-xs text-red-600">{error}</div>}
+      {profiles.active && unmapped.length > 0 && (
+        <div className="text-xs text-amber-700">
+          No emoji in "{profiles.active}" for: {unmapped.join(", ")}
+        </div>
+      )}
 
       <div className="flex-1 flex flex-col gap-3 overflow-y-auto min-h-0">
         {chunks.map((chunk, index) => (
@@ -72,7 +178,7 @@ export function DiscordExport() {
       </div>
 
       <div className="text-xs text-black/40 text-center">
-        {discordText.length} total chars
+        {totalChars} total chars
       </div>
     </div>
   );
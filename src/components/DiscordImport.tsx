@@ -0,0 +1,61 @@
+import { useState } from "react";
+import { importDiscordText } from "../document";
+
+interface DiscordImportProps {
+  /** Runs after the imported grid has been loaded into the store. */
+  onLoaded: () => void;
+}
+
+/** Loads pasted Discord emoji art as a new grid. */
+export function DiscordImport({ onLoaded }: DiscordImportProps) {
+  const [open, setOpen] = useState(false);
+  const [text, setText] = useState("");
+  const [unknown, setUnknown] = useState<string[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  const load = async () => {
+    try {
+      setUnknown(await importDiscordText(text));
+      onLoaded();
+      setError(null);
+      setText("");
+    } catch (err) {
+      setUnknown([]);
+      setError(String(err));
+    }
+  };
+
+  return (
+    <div className="flex flex-col gap-2">
+      <button
+        onClick={() => setOpen(!open)}
+        className="text-left text-xs font-medium text-black/50 hover:text-black/70"
+      >
+        {open ? "▾" : "▸"} Import from Discord
+      </button>
+      {open && (
+        <>
+          <textarea
+            value={text}
+            onChange={(e) => setText(e.target.value)}
+            placeholder="Paste emoji messages here"
+            className="w-full min-h-[80px] p-2 text-xs font-mono bg-white border border-black/20 rounded resize-y focus:outline-none focus:border-black/40"
+          />
+          <button
+            onClick={load}
+            disabled={!text.trim()}
+            className="w-full py-1.5 text-xs rounded transition-colors bg-black/10 text-black/70 hover:bg-black/20 active:bg-black/30 disabled:opacity-50"
+          >
+            Load as New Grid
+          </button>
+        </>
+      )}
+      {error && <div className="text-xs text-red-600">{error}</div>}
+      {unknown.length > 0 && (
+        <div className="text-xs text-amber-700">
+          Left empty for unknown emoji: {unknown.join(", ")}
+        </div>
+      )}
+    </div>
+  );
+}
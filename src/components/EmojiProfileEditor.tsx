@@ -0,0 +1,137 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { EmojiProfile } from "../bindings/EmojiProfile";
+import type { EmojiProfiles } from "../bindings/EmojiProfiles";
+
+interface EmojiProfileEditorProps {
+  profiles: EmojiProfiles;
+  onChange: (profiles: EmojiProfiles) => void;
+}
+
+function profileToText(profile: EmojiProfile | undefined): string {
+  if (!profile) return "";
+  return Object.entries(profile.emoji)
+    .map(([blockId, emoji]) => `${blockId} ${emoji}`)
+    .join("\n");
+}
+
+function textToProfile(text: string): EmojiProfile {
+  const emoji: Record<string, string> = {};
+  for (const line of text.split("\n")) {
+    const [blockId, token] = line.trim().split(/\s+/);
+    if (blockId && token) emoji[blockId] = token;
+  }
+  return { emoji };
+}
+
+/** Picks the active emoji profile and edits profiles as `blockId emoji` lines. */
+export function EmojiProfileEditor({
+  profiles,
+  onChange,
+}: EmojiProfileEditorProps) {
+  const names = Object.keys(profiles.profiles);
+  const [editing, setEditing] = useState(false);
+  const [name, setName] = useState("");
+  const [text, setText] = useState("");
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    if (editing) return;
+    setName(profiles.active ?? "");
+    setText(
+      profileToText(
+        profiles.active ? profiles.profiles[profiles.active] : undefined,
+      ),
+    );
+  }, [profiles, editing]);
+
+  const run = (command: string, args: Record<string, unknown>) =>
+    invoke<EmojiProfiles>(command, args)
+      .then((updated) => {
+        setError(null);
+        onChange(updated);
+        return true;
+      })
+      .catch((err) => {
+        setError(String(err));
+        return false;
+      });
+
+  const save = async () => {
+    const saved = await run("save_emoji_profile", {
+      name,
+      profile: textToProfile(text),
+    });
+    if (!saved) return;
+    if (await run("set_active_emoji_profile", { name: name.trim() })) {
+      setEditing(false);
+    }
+  };
+
+  return (
+    <div className="flex flex-col gap-2">
+      <h3 className="text-xs font-medium text-black/50">Emoji Profile</h3>
+      <div className="flex items-center gap-2">
+        <select
+          value={profiles.active ?? ""}
+          onChange={(e) =>
+            run("set_active_emoji_profile", { name: e.target.value || null })
+          }
+          className="flex-1 px-1 py-1 text-xs bg-white border border-black/20 rounded"
+        >
+          <option value="">None (:blockId:)</option>
+          {names.map((profileName) => (
+            <option key={profileName} value={profileName}>
+              {profileName}
+            </option>
+          ))}
+        </select>
+        <button
+          onClick={() => setEditing(!editing)}
+          className="px-2 py-1 text-xs rounded transition-colors bg-black/10 text-black/60 hover:bg-black/20"
+        >
+          {editing ? "Cancel" : "Edit"}
+        </button>
+      </div>
+
+      {editing && (
+        <div className="flex flex-col gap-2">
+          <input
+            value={name}
+            onChange={(e) => setName(e.target.value)}
+            placeholder="Profile name"
+            className="px-2 py-1 text-xs bg-white border border-black/20 rounded"
+          />
+          <textarea
+            value={text}
+            onChange={(e) => setText(e.target.value)}
+            placeholder={"12 <:blank:123456789>\ncol_blue_hi blue_hi"}
+            className="w-full min-h-[100px] p-2 text-xs font-mono bg-white border border-black/20 rounded resize-y focus:outline-none focus:border-black/40"
+          />
+          <div className="flex gap-2">
+            <button
+              onClick={save}
+              className="flex-1 py-1.5 text-xs rounded transition-colors bg-black text-white hover:bg-black/80"
+            >
+              Save
+            </button>
+            {names.includes(name.trim()) && (
+              <button
+                onClick={async () => {
+                  if (await run("delete_emoji_profile", { name: name.trim() })) {
+                    setEditing(false);
+                  }
+                }}
+                className="px-3 py-1.5 text-xs rounded transition-colors bg-black/10 text-red-600 hover:bg-black/20"
+              >
+                Delete
+              </button>
+            )}
+          </div>
+        </div>
+      )}
+
+      {error && <div className="text-xs text-red-600">{error}</div>}
+    </div>
+  );
+}
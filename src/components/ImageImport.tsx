@@ -0,0 +1,94 @@
+import { useEffect, useState } from "react";
+import { useAppStore } from "../store/appStore";
+import { useGridStore } from "../store/gridStore";
+import { loadBlockPack } from "../blocks";
+import type { BlockGroup } from "../bindings/BlockGroup";
+import type { Dither } from "../bindings/Dither";
+
+const DITHERS: { id: Dither; label: string }[] = [
+  { id: "none", label: "None" },
+  { id: "floydSteinberg", label: "Floyd–Steinberg" },
+  { id: "atkinson", label: "Atkinson" },
+  { id: "bayer", label: "Ordered (Bayer)" },
+];
+
+const ALL_BLOCKS = "_all";
+
+interface ImageImportProps {
+  onImport: () => void;
+}
+
+/** Settings for rebuilding a picture out of blocks. */
+export function ImageImport({ onImport }: ImageImportProps) {
+  const { imageImport, setImageImport } = useAppStore();
+  const packId = useGridStore((state) => state.pack.id);
+  const [groups, setGroups] = useState<BlockGroup[]>([]);
+
+  useEffect(() => {
+    loadBlockPack(packId).then((pack) => setGroups(pack.groups));
+  }, [packId]);
+
+  const group =
+    groups.find((group) => imageImport.blocks?.join() === group.blocks.join())
+      ?.name ?? ALL_BLOCKS;
+
+  return (
+    <section className="p-3">
+      <h3 className="text-xs font-medium text-black/50 mb-2">Image Import</h3>
+      <div className="flex flex-col gap-2 text-xs">
+        <label className="flex flex-col gap-1 text-black/40">
+          Blocks
+          <select
+            value={group}
+            onChange={(e) =>
+              setImageImport({
+                blocks: groups.find((group) => group.name === e.target.value)
+                  ?.blocks,
+              })
+            }
+            className="px-1 py-1 text-black bg-white border border-black/20 rounded"
+          >
+            <option value={ALL_BLOCKS}>All blocks</option>
+            {groups.map(({ name }) => (
+              <option key={name} value={name}>
+                {name}
+              </option>
+            ))}
+          </select>
+        </label>
+        <label className="flex flex-col gap-1 text-black/40">
+          Dithering
+          <select
+            value={imageImport.dither}
+            onChange={(e) =>
+              setImageImport({ dither: e.target.value as Dither })
+            }
+            className="px-1 py-1 text-black bg-white border border-black/20 rounded"
+          >
+            {DITHERS.map(({ id, label }) => (
+              <option key={id} value={id}>
+                {label}
+              </option>
+            ))}
+          </select>
+        </label>
+        <label className="flex items-center gap-1.5 text-black/70">
+          <input
+            type="checkbox"
+            checked={imageImport.matchTexture}
+            onChange={(e) =>
+              setImageImport({ matchTexture: e.target.checked })
+            }
+          />
+          Match block texture
+        </label>
+      </div>
+      <button
+        onClick={onImport}
+        className="w-full mt-3 py-2 text-xs font-medium rounded transition-colors bg-blue-200 text-blue-800 hover:bg-blue-300 active:bg-blue-300"
+      >
+        Import Image…
+      </button>
+    </section>
+  );
+}
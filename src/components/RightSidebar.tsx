@@ -1,6 +1,7 @@
 import { useAppStore, type Tool } from "../store/appStore";
-import { useGridStore } from "../store/gridStore";
 import { DiscordExport } from "./DiscordExport";
+import { DiscordImport } from "./DiscordImport";
+import { ImageImport } from "./ImageImport";
 
 const toolModules = import.meta.glob<{ default: string }>(
   "../icons/tools/*.png",
@@ -27,19 +28,38 @@ const TOOLS: { id: Tool; label: string }[] = [
   { id: "fill", label: "Flood Fill" },
 ];
 
+const MAX_EXPORT_CELL_SIZE = 256;
+const EXPORT_LINE = { color: "#00000040", width: 1 };
+const EXPORT_BORDER = { color: "#000000", width: 2 };
+
 interface RightSidebarProps {
   onClearGrid: () => void;
   onResizeGrid: (cols: number, rows: number) => void;
   onExportPng: () => void;
+  onExportAnimation: () => void;
+  onExportSvg: () => void;
+  onImportImage: () => void;
+  onGridLoaded: () => void;
 }
 
 export function RightSidebar({
   onClearGrid,
   onResizeGrid,
   onExportPng,
+  onExportAnimation,
+  onExportSvg,
+  onImportImage,
+  onGridLoaded,
 }: RightSidebarProps) {
-  const { currentTool, setTool, gridCols, gridRows, setGridSize } =
-    useAppStore();
+  const {
+    currentTool,
+    setTool,
+    gridCols,
+    gridRows,
+    setGridSize,
+    pngExport,
+    setPngExport,
+  } = useAppStore();
 
   const handleGridSizeChange = (dimension: "cols" | "rows", value: string) => {
     const num = parseInt(value, 10);
@@ -52,6 +72,12 @@ export function RightSidebar({
     onResizeGrid(newCols, newRows);
   };
 
+  const handleCellSizeChange = (value: string) => {
+    const num = parseInt(value, 10);
+    if (isNaN(num) || num < 1 || num > MAX_EXPORT_CELL_SIZE) return;
+    setPngExport({ cellSize: num });
+  };
+
   return (
     <div className="flex flex-col h-full overflow-hidden">
       <section className="p-3">
@@ -108,10 +134,7 @@ export function RightSidebar({
           </div>
         </div>
         <button
-          onClick={() => {
-            useGridStore.getState().pushHistory();
-            onClearGrid();
-          }}
+          onClick={onClearGrid}
           className="w-full mt-3 py-1.5 text-xs rounded transition-colors bg-red-500/10 text-red-600 hover:bg-red-500/20 active:bg-red-500/30"
         >
           Clear Grid
@@ -121,18 +144,91 @@ export function RightSidebar({
       <hr className="border-black/10" />
 
       <section className="p-3">
+        <h3 className="text-xs font-medium text-black/50 mb-2">Image Export</h3>
+        <label className="text-xs text-black/40">Cell size (px)</label>
+        <input
+          type="number"
+          min={1}
+          max={MAX_EXPORT_CELL_SIZE}
+          value={pngExport.cellSize}
+          onChange={(e) => handleCellSizeChange(e.target.value)}
+          className="w-full px-2 py-1 text-sm border border-black/20 rounded focus:outline-none focus:border-black/40"
+        />
+        <div className="flex flex-col gap-1 mt-2 text-xs text-black/70">
+          <label className="flex items-center gap-1.5">
+            <input
+              type="checkbox"
+              checked={pngExport.gridLines !== undefined}
+              onChange={(e) =>
+                setPngExport({
+                  gridLines: e.target.checked ? EXPORT_LINE : undefined,
+                })
+              }
+            />
+            Grid lines
+          </label>
+          <label className="flex items-center gap-1.5">
+            <input
+              type="checkbox"
+              checked={pngExport.border !== undefined}
+              onChange={(e) =>
+                setPngExport({
+                  border: e.target.checked ? EXPORT_BORDER : undefined,
+                })
+              }
+            />
+            Border
+          </label>
+          <label className="flex items-center gap-1.5">
+            <input
+              type="checkbox"
+              checked={pngExport.background !== undefined}
+              onChange={(e) =>
+                setPngExport({
+                  background: e.target.checked ? "#ffffff" : undefined,
+                })
+              }
+            />
+            Background
+            {pngExport.background !== undefined && (
+              <input
+                type="color"
+                value={pngExport.background}
+                onChange={(e) => setPngExport({ background: e.target.value })}
+                className="ml-auto w-6 h-4"
+              />
+            )}
+          </label>
+        </div>
         <button
           onClick={onExportPng}
-          className="w-full py-2 text-xs font-medium rounded transition-colors bg-blue-200 text-blue-800 hover:bg-blue-300 active:bg-blue-300"
+          className="w-full mt-3 py-2 text-xs font-medium rounded transition-colors bg-blue-200 text-blue-800 hover:bg-blue-300 active:bg-blue-300"
         >
           Export as PNG
         </button>
+        <button
+          onClick={onExportAnimation}
+          className="w-full mt-1 py-2 text-xs font-medium rounded transition-colors bg-blue-200 text-blue-800 hover:bg-blue-300 active:bg-blue-300"
+        >
+          Export Animation
+        </button>
+        <button
+          onClick={onExportSvg}
+          className="w-full mt-1 py-2 text-xs font-medium rounded transition-colors bg-blue-200 text-blue-800 hover:bg-blue-300 active:bg-blue-300"
+        >
+          Export as SVG
+        </button>
       </section>
 
       <hr className="border-black/10" />
 
-      <section className="flex-1 min-h-0 flex flex-col p-3">
+      <ImageImport onImport={onImportImage} />
+
+      <hr className="border-black/10" />
+
+      <section className="flex-1 min-h-0 flex flex-col gap-3 p-3">
         <DiscordExport />
+        <DiscordImport onLoaded={onGridLoaded} />
       </section>
     </div>
   );
@@ -0,0 +1,301 @@
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { open, save } from "@tauri-apps/plugin-dialog";
+import createDebug from "debug";
+import { useGridStore } from "./store/gridStore";
+import { useAppStore } from "./store/appStore";
+import type { AnimationFormat } from "./bindings/AnimationFormat";
+import type { BlockPackRef } from "./bindings/BlockPackRef";
+import type { ConvertOptions } from "./bindings/ConvertOptions";
+
+const debug = createDebug("binblock:document");
+
+const PROJECT_FILTERS = [{ name: "binblock Project", extensions: ["binblock"] }];
+
+export type GridSnapshot = {
+  cols: number;
+  rows: number;
+  cells: Record<string, string>;
+  pack: BlockPackRef;
+};
+
+/**
+ * Replace the editor contents with a grid loaded by the backend.
+ */
+export function loadGrid(grid: GridSnapshot): void {
+  useGridStore.getState().setGrid(grid.cols, grid.rows, grid.cells, grid.pack);
+  useAppStore.getState().setGridSize(grid.cols, grid.rows);
+}
+
+/**
+ * Draw the open document with block pack `packId` instead.
+ */
+export async function setDocumentPack(packId: string): Promise<void> {
+  await useGridStore.getState().commitEdit();
+  const grid = await invoke<GridSnapshot>("set_document_pack", { packId });
+  loadGrid(grid);
+  debug("setDocumentPack: switched to %s", packId);
+}
+
+/**
+ * Start over with a blank, untitled grid.
+ */
+export async function newDocument(): Promise<void> {
+  const grid = await invoke<GridSnapshot>("new_document");
+  loadGrid(grid);
+  debug("newDocument: %dx%d", grid.cols, grid.rows);
+}
+
+/**
+ * Ask for a .binblock file and load it. Returns false if the user cancelled.
+ */
+export async function openDocument(): Promise<boolean> {
+  const path = await open({ multiple: false, filters: PROJECT_FILTERS });
+  if (!path) {
+    debug("openDocument: user cancelled open dialog");
+    return false;
+  }
+
+  const grid = await invoke<GridSnapshot>("open_document", { path });
+  loadGrid(grid);
+  debug("openDocument: loaded %s (%dx%d)", path, grid.cols, grid.rows);
+  return true;
+}
+
+/**
+ * Load the `index`th entry of the File > Open Recent menu.
+ */
+export async function openRecentDocument(index: number): Promise<void> {
+  const grid = await invoke<GridSnapshot>("open_recent", { index });
+  loadGrid(grid);
+  debug("openRecentDocument: loaded entry %d (%dx%d)", index, grid.cols, grid.rows);
+}
+
+/**
+ * Replace the editor contents with a grid read from pasted Discord emoji
+ * text. Returns the emoji that matched no block.
+ */
+export async function importDiscordText(text: string): Promise<string[]> {
+  const { grid, unknown } = await invoke<{
+    grid: GridSnapshot;
+    unknown: string[];
+  }>("import_discord_text", { text });
+  loadGrid(grid);
+  debug(
+    "importDiscordText: loaded %dx%d, %d unknown emoji",
+    grid.cols,
+    grid.rows,
+    unknown.length
+  );
+  return unknown;
+}
+
+/**
+ * Ask for a picture and rebuild it out of the document pack's blocks at the
+ * current grid size, using the image import settings. Returns false if the
+ * user cancelled.
+ */
+export async function importImage(): Promise<boolean> {
+  const path = await open({
+    multiple: false,
+    filters: [
+      { name: "Image", extensions: ["png", "jpg", "jpeg", "gif", "webp"] },
+    ],
+  });
+  if (!path) {
+    debug("importImage: user cancelled open dialog");
+    return false;
+  }
+
+  const { cols, rows } = useGridStore.getState();
+  const options: ConvertOptions = {
+    ...useAppStore.getState().imageImport,
+    cols,
+    rows,
+  };
+  const grid = await invoke<GridSnapshot>("import_image", { path, options });
+  loadGrid(grid);
+  debug("importImage: converted %s to %dx%d", path, grid.cols, grid.rows);
+  return true;
+}
+
+/**
+ * Save to the path the document was opened from or last saved to,
+ * falling back to Save As for documents that have never been saved.
+ */
+export async function saveDocument(): Promise<boolean> {
+  const path = await invoke<string | null>("document_path");
+  if (!path) {
+    return saveDocumentAs();
+  }
+
+  await useGridStore.getState().commitEdit();
+  await invoke("save_document", { path });
+  debug("saveDocument: saved to %s", path);
+  return true;
+}
+
+export async function saveDocumentAs(): Promise<boolean> {
+  const { cols, rows } = useGridStore.getState();
+  const path = await save({
+    defaultPath: `binblock-${cols}x${rows}.binblock`,
+    filters: PROJECT_FILTERS,
+  });
+  if (!path) {
+    debug("saveDocumentAs: user cancelled save dialog");
+    return false;
+  }
+
+  await useGridStore.getState().commitEdit();
+  await invoke("save_document", { path });
+  debug("saveDocumentAs: saved to %s", path);
+  return true;
+}
+
+/**
+ * Render the grid to a PNG with the current export settings.
+ */
+export async function exportPng(): Promise<boolean> {
+  const { cols, rows } = useGridStore.getState();
+  const path = await save({
+    defaultPath: `binblock-${cols}x${rows}.png`,
+    filters: [{ name: "PNG Image", extensions: ["png"] }],
+  });
+  if (!path) {
+    debug("exportPng: user cancelled save dialog");
+    return false;
+  }
+
+  const options = useAppStore.getState().pngExport;
+  await useGridStore.getState().commitEdit();
+  await invoke("render_png", { path, options });
+  debug("exportPng: saved to %s at %dpx per cell", path, options.cellSize);
+  return true;
+}
+
+/**
+ * Render one loop of the grid's animated blocks to a GIF or APNG, using the
+ * PNG export settings for cell size and decorations.
+ */
+export async function exportAnimation(): Promise<boolean> {
+  const { cols, rows } = useGridStore.getState();
+  const path = await save({
+    defaultPath: `binblock-${cols}x${rows}.gif`,
+    filters: [
+      { name: "GIF Image", extensions: ["gif"] },
+      { name: "Animated PNG", extensions: ["png", "apng"] },
+    ],
+  });
+  if (!path) {
+    debug("exportAnimation: user cancelled save dialog");
+    return false;
+  }
+
+  const format: AnimationFormat = path.toLowerCase().endsWith(".gif")
+    ? "gif"
+    : "apng";
+  const options = useAppStore.getState().pngExport;
+  await useGridStore.getState().commitEdit();
+  const frames = await invoke<number>("export_animation", {
+    path,
+    format,
+    options,
+  });
+  debug("exportAnimation: saved %d %s frames to %s", frames, format, path);
+  return true;
+}
+
+/**
+ * Write the grid as an SVG with the block images embedded, using the PNG
+ * export settings for display size and decorations.
+ */
+export async function exportSvg(): Promise<boolean> {
+  const { cols, rows } = useGridStore.getState();
+  const path = await save({
+    defaultPath: `binblock-${cols}x${rows}.svg`,
+    filters: [{ name: "SVG Image", extensions: ["svg"] }],
+  });
+  if (!path) {
+    debug("exportSvg: user cancelled save dialog");
+    return false;
+  }
+
+  const options = useAppStore.getState().pngExport;
+  await useGridStore.getState().commitEdit();
+  await invoke("export_svg", { path, options });
+  debug("exportSvg: saved to %s", path);
+  return true;
+}
+
+/**
+ * Write the grid's Discord emoji text to a .txt file.
+ */
+export async function exportDiscordText(): Promise<boolean> {
+  const { cols, rows } = useGridStore.getState();
+  const path = await save({
+    defaultPath: `binblock-${cols}x${rows}.txt`,
+    filters: [{ name: "Text", extensions: ["txt"] }],
+  });
+  if (!path) {
+    debug("exportDiscordText: user cancelled save dialog");
+    return false;
+  }
+
+  await useGridStore.getState().commitEdit();
+  await invoke("export_discord_text", { path });
+  debug("exportDiscordText: saved to %s", path);
+  return true;
+}
+
+/**
+ * Load grids the backend changes on its own, such as after undo/redo from
+ * the menu. `onChanged` runs after the store has been updated.
+ */
+export async function initDocumentListener(
+  onChanged: () => void
+): Promise<UnlistenFn> {
+  return listen<GridSnapshot>("document-changed", (event) => {
+    loadGrid(event.payload);
+    onChanged();
+  });
+}
+
+type RecoveryInfo = {
+  documentPath: string | null;
+  cols: number;
+  rows: number;
+  modifiedAt: number;
+};
+
+/**
+ * Offer to restore work left behind by a crashed session. `onRestored` runs
+ * after the recovered grid has been loaded into the store.
+ */
+export async function initRecovery(onRestored: () => void): Promise<UnlistenFn> {
+  const unlisten = await listen<RecoveryInfo>(
+    "recovery-available",
+    async (event) => {
+      const { documentPath, cols, rows, modifiedAt } = event.payload;
+      const name = documentPath ?? "an unsaved grid";
+      const when = new Date(modifiedAt * 1000).toLocaleString();
+      debug("recovery available: %s (%dx%d)", name, cols, rows);
+
+      const restore = window.confirm(
+        `binblock++ didn't shut down cleanly. Restore ${name} (${cols}×${rows}) from ${when}?`
+      );
+      if (!restore) {
+        await invoke("discard_recovery");
+        return;
+      }
+
+      const grid = await invoke<GridSnapshot | null>("restore_recovery");
+      if (grid) {
+        loadGrid(grid);
+        onRestored();
+      }
+    }
+  );
+
+  await invoke("check_recovery");
+  return unlisten;
+}
@@ -4,3 +4,11 @@ import { twMerge } from "tailwind-merge";
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs));
 }
+
+/**
+ * Tell the user something they asked for failed.
+ */
+export function showError(error: unknown): void {
+  console.error(error);
+  window.alert(String(error));
+}
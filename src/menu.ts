@@ -1,8 +1,19 @@
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
-import { useGridStore } from "./store/gridStore";
 import type { CanvasController } from "./canvas";
-
-type MenuEventPayload = string;
+import type { MenuAction } from "./bindings/MenuAction";
+import {
+  exportAnimation,
+  exportDiscordText,
+  exportPng,
+  exportSvg,
+  importImage,
+  newDocument,
+  openDocument,
+  openRecentDocument,
+  saveDocument,
+  saveDocumentAs,
+} from "./document";
+import { showError } from "./lib/utils";
 
 let unlistenFn: UnlistenFn | null = null;
 let canvasRef: CanvasController | null = null;
@@ -17,23 +28,50 @@ export async function initMenuListeners(): Promise<void> {
     unlistenFn();
   }
 
-  unlistenFn = await listen<MenuEventPayload>("menu-event", (event) => {
-    const menuId = event.payload;
-    console.log("Menu event:", menuId);
+  unlistenFn = await listen<MenuAction>("menu-event", (event) => {
+    const action = event.payload;
+    console.log("Menu event:", action);
 
-    switch (menuId) {
-      case "edit:undo":
-        handleUndo();
+    switch (action.type) {
+      case "new":
+        handleNew();
+        break;
+      case "open":
+        handleOpen();
+        break;
+      case "openRecent":
+        handleOpenRecent(action.index);
+        break;
+      case "importImage":
+        handleImportImage();
+        break;
+      case "save":
+        saveDocument().catch(showError);
+        break;
+      case "saveAs":
+        saveDocumentAs().catch(showError);
+        break;
+      case "exportPng":
+        exportPng().catch(showError);
+        break;
+      case "exportAnimation":
+        exportAnimation().catch(showError);
+        break;
+      case "exportSvg":
+        exportSvg().catch(showError);
         break;
-      case "edit:redo":
-        handleRedo();
+      case "exportDiscord":
+        exportDiscordText().catch(showError);
         break;
-      case "edit:clear":
+      case "clear":
         handleClear();
         break;
-      case "view:reset":
+      case "resetView":
         handleResetView();
         break;
+      default:
+        // closeWindow, quit, clearRecent, undo and redo are handled by the backend
+        console.warn("Unhandled menu action:", action);
     }
   });
 }
@@ -45,27 +83,45 @@ export function cleanupMenuListeners(): void {
   }
 }
 
-function handleUndo(): void {
-  const store = useGridStore.getState();
-  if (store.canUndo()) {
-    store.undo();
-    // Sync canvas with new store state
+async function handleNew(): Promise<void> {
+  try {
+    await newDocument();
     canvasRef?.syncFromStore();
+  } catch (error) {
+    showError(error);
   }
 }
 
-function handleRedo(): void {
-  const store = useGridStore.getState();
-  if (store.canRedo()) {
-    store.redo();
-    // Sync canvas with new store state
+async function handleOpen(): Promise<void> {
+  try {
+    if (await openDocument()) {
+      canvasRef?.syncFromStore();
+    }
+  } catch (error) {
+    showError(error);
+  }
+}
+
+async function handleOpenRecent(index: number): Promise<void> {
+  try {
+    await openRecentDocument(index);
     canvasRef?.syncFromStore();
+  } catch (error) {
+    showError(error);
+  }
+}
+
+async function handleImportImage(): Promise<void> {
+  try {
+    if (await importImage()) {
+      canvasRef?.syncFromStore();
+    }
+  } catch (error) {
+    showError(error);
   }
 }
 
 function handleClear(): void {
-  // Push history before clearing
-  useGridStore.getState().pushHistory();
   canvasRef?.clearAllCells();
 }
 
@@ -1,4 +1,6 @@
 import { create } from "zustand";
+import type { ConvertOptions } from "../bindings/ConvertOptions";
+import type { RenderOptions } from "../bindings/RenderOptions";
 
 export type Tool =
   | "pencil"
@@ -9,20 +11,43 @@ export type Tool =
   | "circle-filled"
   | "fill";
 
+export const TOOL_LABELS: Record<Tool, string> = {
+  pencil: "Pencil",
+  line: "Line",
+  rect: "Rectangle",
+  "rect-filled": "Fill Rectangle",
+  circle: "Circle",
+  "circle-filled": "Fill Circle",
+  fill: "Flood Fill",
+};
+
+/** Image import settings; the grid size comes from the open grid. */
+export type ImageImportSettings = Omit<ConvertOptions, "cols" | "rows">;
+
 interface AppState {
   currentTool: Tool;
   gridCols: number;
   gridRows: number;
+  pngExport: RenderOptions;
+  imageImport: ImageImportSettings;
 
   setTool: (tool: Tool) => void;
   setGridSize: (cols: number, rows: number) => void;
+  setPngExport: (options: Partial<RenderOptions>) => void;
+  setImageImport: (settings: Partial<ImageImportSettings>) => void;
 }
 
 export const useAppStore = create<AppState>((set) => ({
   currentTool: "pencil",
   gridCols: 8,
   gridRows: 8,
+  pngExport: { cellSize: 100 },
+  imageImport: { matchTexture: true, dither: "none" },
 
   setTool: (tool) => set({ currentTool: tool }),
   setGridSize: (cols, rows) => set({ gridCols: cols, gridRows: rows }),
+  setPngExport: (options) =>
+    set((state) => ({ pngExport: { ...state.pngExport, ...options } })),
+  setImageImport: (settings) =>
+    set((state) => ({ imageImport: { ...state.imageImport, ...settings } })),
 }));
@@ -1,177 +1,108 @@
 import { create } from "zustand";
-import { DEFAULT_BLOCK_ID } from "../blocks";
+import { invoke } from "@tauri-apps/api/core";
+import createDebug from "debug";
+import { showError } from "../lib/utils";
+import { BUILTIN_PACK_ID, DEFAULT_BLOCK_ID } from "../blocks";
+import type { BlockPackRef } from "../bindings/BlockPackRef";
 
-const HISTORY_LIMIT = 50;
+const debug = createDebug("binblock:grid");
 
-type GridSnapshot = {
-  cols: number;
-  rows: number;
-  cells: Record<string, string>;
+type PendingEdit = {
+  label: string;
+  cells: Map<string, { x: number; y: number; blockId: string | null }>;
 };
 
 interface GridState {
   cols: number;
   rows: number;
   cells: Record<string, string>; // "x,y" -> blockId
+  // Pack the document draws with; empty cells show its default block.
+  pack: BlockPackRef;
+  // Bumped whenever the backend's copy of the document has caught up with
+  // the store, for views that read the document from the backend.
+  revision: number;
 
-  // History
-  past: GridSnapshot[];
-  future: GridSnapshot[];
+  // Edit currently being recorded for the backend history
+  pending: PendingEdit | null;
 
   // Actions
   setCell: (x: number, y: number, blockId: string) => void;
-  setGrid: (cols: number, rows: number, cells: Record<string, string>) => void;
+  setGrid: (
+    cols: number,
+    rows: number,
+    cells: Record<string, string>,
+    pack: BlockPackRef
+  ) => void;
   clearGrid: (cols: number, rows: number) => void;
 
-  // History actions
-  pushHistory: () => void;
-  undo: () => void;
-  redo: () => void;
-  canUndo: () => boolean;
-  canRedo: () => boolean;
+  // History actions. Undo/redo themselves live in the backend.
+  beginEdit: (label: string) => void;
+  commitEdit: () => Promise<void>;
 }
 
 export const useGridStore = create<GridState>((set, get) => ({
   cols: 8,
   rows: 8,
   cells: {},
-  past: [],
-  future: [],
+  pack: { id: BUILTIN_PACK_ID, defaultBlock: DEFAULT_BLOCK_ID },
+  revision: 0,
+  pending: null,
 
-  setCell: (x, y, blockId) =>
+  setCell: (x, y, blockId) => {
+    get().pending?.cells.set(`${x},${y}`, { x, y, blockId });
     set((state) => ({
       cells: { ...state.cells, [`${x},${y}`]: blockId },
-      // Clear future on new changes (standard undo/redo behavior)
-      future: [],
-    })),
+    }));
+  },
 
-  setGrid: (cols, rows, cells) =>
-    set(() => ({
+  setGrid: (cols, rows, cells, pack) =>
+    set((state) => ({
       cols,
       rows,
       cells,
-      future: [],
+      pack,
+      revision: state.revision + 1,
     })),
 
-  clearGrid: (cols, rows) =>
+  clearGrid: (cols, rows) => {
+    const { pending, cells } = get();
+    if (pending) {
+      for (const key of Object.keys(cells)) {
+        const [x, y] = key.split(",").map((n) => parseInt(n, 10));
+        pending.cells.set(key, { x, y, blockId: null });
+      }
+    }
     set(() => ({
       cols,
       rows,
       cells: {},
-      future: [],
-    })),
-
-  pushHistory: () =>
-    set((state) => {
-      const snapshot: GridSnapshot = {
-        cols: state.cols,
-        rows: state.rows,
-        cells: { ...state.cells },
-      };
-      const newPast = [...state.past, snapshot];
-      if (newPast.length > HISTORY_LIMIT) {
-        newPast.shift();
-      }
-      return { past: newPast, future: [] };
-    }),
-
-  undo: () => {
-    const state = get();
-    if (state.past.length === 0) return;
-
-    const newPast = [...state.past];
-    const previous = newPast.pop()!;
-    const currentSnapshot: GridSnapshot = {
-      cols: state.cols,
-      rows: state.rows,
-      cells: { ...state.cells },
-    };
-
-    set({
-      cols: previous.cols,
-      rows: previous.rows,
-      cells: previous.cells,
-      past: newPast,
-      future: [currentSnapshot, ...state.future],
-    });
+    }));
   },
 
-  redo: () => {
-    const state = get();
-    if (state.future.length === 0) return;
-
-    const newFuture = [...state.future];
-    const next = newFuture.shift()!;
-    const currentSnapshot: GridSnapshot = {
-      cols: state.cols,
-      rows: state.rows,
-      cells: { ...state.cells },
-    };
-
-    set({
-      cols: next.cols,
-      rows: next.rows,
-      cells: next.cells,
-      past: [...state.past, currentSnapshot],
-      future: newFuture,
-    });
+  beginEdit: (label) => {
+    get().commitEdit();
+    set({ pending: { label, cells: new Map() } });
   },
 
-  canUndo: () => get().past.length > 0,
-  canRedo: () => get().future.length > 0,
-}));
-
-/**
- * Convert the current grid state to Discord emoji text format.
- * Each cell becomes :blockId: using the block's string ID.
- * Empty cells default to the DEFAULT_BLOCK_ID.
- */
-export function toDiscordText(
-  cols: number,
-  rows: number,
-  cells: Record<string, string>
-): string {
-  const lines: string[] = [];
-
-  for (let y = 0; y < rows; y++) {
-    let row = "";
-    for (let x = 0; x < cols; x++) {
-      const blockId = cells[`${x},${y}`] ?? DEFAULT_BLOCK_ID;
-      row += `:${blockId}:`;
+  commitEdit: async () => {
+    const { pending } = get();
+    if (!pending) return;
+    set({ pending: null });
+    if (pending.cells.size === 0) return;
+
+    try {
+      await invoke("apply_edit", {
+        label: pending.label,
+        cells: [...pending.cells.values()],
+      });
+      set((state) => ({ revision: state.revision + 1 }));
+    } catch (error) {
+      // The backend kept its document as it was, so put the canvas back to
+      // match what will be saved.
+      debug("apply_edit failed: %O", error);
+      showError(error);
+      await invoke("resync_document").catch(showError);
     }
-    lines.push(row);
-  }
-
-  return lines.join("\n");
-}
-
-/**
- * Split Discord text into chunks that fit within the character limit.
- * Splits on newline boundaries to keep rows intact.
- */
-export function splitDiscordText(text: string, charLimit: number): string[] {
-  if (text.length <= charLimit) {
-    return [text];
-  }
-
-  const lines = text.split("\n");
-  const chunks: string[] = [];
-  let currentChunk = "";
-
-  for (const line of lines) {
-    if (currentChunk.length + line.length + 1 > charLimit) {
-      if (currentChunk) {
-        chunks.push(currentChunk);
-      }
-      currentChunk = line;
-    } else {
-      currentChunk = currentChunk ? `${currentChunk}\n${line}` : line;
-    }
-  }
-
-  if (currentChunk) {
-    chunks.push(currentChunk);
-  }
+  },
+}));
 
-  return chunks;
-}